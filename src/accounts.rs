@@ -0,0 +1,164 @@
+use std::{
+    cmp::Ordering,
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, info, warn};
+
+use crate::{
+    config::{Bot, Config},
+    pom::version,
+    utils::fail,
+};
+
+const AUTO_LOGIN_PATH: &str = "./config/Console/AutoLogin.yml";
+const BOTS_PATH: &str = "./bots";
+//accounts 格式的 AutoLogin.yml 从这个版本开始支持，更早的版本使用 plainPasswords 与 md5Passwords
+const AUTO_LOGIN_MIN_CONSOLE: &str = "2.10.0";
+const CONSOLE: &str = "net.mamoe:mirai-console";
+
+//YAML 双引号字符串
+fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn password(account: &str, bot: &Bot) -> String {
+    if let Some(name) = bot.password_env.as_deref() {
+        return env::var(name).unwrap_or_else(|_| {
+            fail(format!("bots.{} 的密码环境变量 {} 没有设置", account, name))
+        });
+    }
+    let path = bot
+        .password_file
+        .as_deref()
+        .unwrap_or_else(|| fail(format!("bots.{} 没有指定密码", account)));
+    let password = fs::read_to_string(path).unwrap_or_else(|e| {
+        fail(format!(
+            "读取 bots.{} 的密码文件 {} 失败，{}",
+            account, path, e
+        ))
+    });
+    //文件末尾的换行不算密码
+    password.trim_end_matches(['\r', '\n']).to_owned()
+}
+
+//去掉其它用户的读写权限，之前手动创建的文件可能是 0644
+#[cfg(unix)]
+fn restrict(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Ok(metadata) = path.metadata() {
+        if metadata.permissions().mode() & 0o077 != 0 {
+            info!("收紧 {} 的权限为 0600", path.display());
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+                .unwrap_or_else(|e| panic!("修改 {} 的权限失败，{}", path.display(), e));
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn restrict(_path: &Path) {}
+
+//内容不同时先写入同目录的临时文件再替换，unix 上临时文件的权限是 0600，其它用户读不到密码
+//返回是否写入了新内容
+fn write_private(path: &Path, content: &[u8]) -> bool {
+    if fs::read(path).ok().as_deref() == Some(content) {
+        debug!("{} 没有变化", path.display());
+        restrict(path);
+        return false;
+    }
+    let dir = path.parent().unwrap();
+    fs::create_dir_all(dir).unwrap_or_else(|e| panic!("创建 {} 失败，{}", dir.display(), e));
+    let mut file = tempfile::Builder::new()
+        .prefix(".mirua-")
+        .tempfile_in(dir)
+        .expect("创建临时文件失败");
+    file.write_all(content).expect("写入文件失败");
+    file.persist(path)
+        .unwrap_or_else(|e| panic!("写入 {} 失败，{}", path.display(), e));
+    true
+}
+
+fn auto_login(config: &Config) -> String {
+    let mut buf =
+        String::from("# 由 mirua 根据 mirua.toml 中的 [bots] 生成，修改会被覆盖\naccounts:\n");
+    for (account, bot) in config.bots.iter() {
+        let kind = bot.password_kind.as_deref().unwrap_or("plain");
+        buf.push_str(&format!("  - account: {}\n", account));
+        buf.push_str("    password:\n");
+        buf.push_str(&format!("      kind: {}\n", kind.to_uppercase()));
+        buf.push_str(&format!(
+            "      value: {}\n",
+            quote(&password(account, bot))
+        ));
+        buf.push_str("    configuration:\n");
+        if let Some(protocol) = bot.protocol.as_deref() {
+            buf.push_str(&format!("      protocol: {}\n", protocol.to_uppercase()));
+        }
+        buf.push_str("      device: device.json\n");
+        buf.push_str(&format!("      enable: {}\n", bot.enable.unwrap_or(true)));
+        if let Some(strategy) = bot.heartbeat_strategy.as_deref() {
+            buf.push_str(&format!(
+                "      heartbeatStrategy: {}\n",
+                strategy.to_uppercase()
+            ));
+        }
+    }
+    buf
+}
+
+//device.json 与配置中指定的文件不同时复制过去
+fn sync_device(account: &str, source: &str) {
+    let content = fs::read(source).unwrap_or_else(|e| {
+        fail(format!(
+            "读取 bots.{} 的设备信息 {} 失败，{}",
+            account, source, e
+        ))
+    });
+    let path: PathBuf = Path::new(BOTS_PATH).join(account).join("device.json");
+    if write_private(&path, &content) {
+        info!("复制 {} 到 {}", source, path.display());
+    }
+}
+
+//[bots] 为空时不动 AutoLogin.yml，方便手动维护
+pub fn sync(config: &Config) {
+    if config.bots.is_empty() {
+        return;
+    }
+    let console = config
+        .mirai
+        .full
+        .get(CONSOLE)
+        .or_else(|| config.mirai.maven.get(CONSOLE));
+    if let Some(console) = console {
+        if version::compare(console, AUTO_LOGIN_MIN_CONSOLE) == Ordering::Less {
+            warn!(
+                "mirai-console {} 不支持生成的 AutoLogin.yml 格式，[bots] 需要 {} 及以上版本，自动登录不会生效",
+                console, AUTO_LOGIN_MIN_CONSOLE
+            );
+        }
+    }
+    for (account, bot) in config.bots.iter() {
+        if let Some(device) = bot.device.as_deref() {
+            sync_device(account, device);
+        }
+    }
+    if write_private(Path::new(AUTO_LOGIN_PATH), auto_login(config).as_bytes()) {
+        info!("根据 [bots] 生成 {}", AUTO_LOGIN_PATH);
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{info, warn};
+
+#[cfg(unix)]
+use flate2::{write::GzEncoder, Compression};
+#[cfg(unix)]
+use tar::Builder;
+
+#[cfg(windows)]
+use std::io;
+#[cfg(windows)]
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::{
+    config::{Backup, Config},
+    utils::{self, fail},
+};
+
+//mirai-console 的配置、插件数据与设备信息
+const BACKUP_DIRS: [&str; 3] = ["config", "data", "bots"];
+const PREFIX: &str = "mirai-";
+#[cfg(unix)]
+const EXTENSION: &str = ".tar.gz";
+#[cfg(windows)]
+const EXTENSION: &str = ".zip";
+//上次启动时的 mirai 与插件版本，变化时自动备份
+const VERSIONS_NAME: &str = ".mirua-versions";
+
+//UTC 时间，格式为 20210102-150405
+fn timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or(0);
+    let (days, secs) = ((secs / 86400) as i64, secs % 86400);
+    //公历日期换算，见 http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+//按文件名排序即按时间排序
+fn snapshots(config: &Backup) -> Vec<PathBuf> {
+    let mut snapshots: Vec<_> = fs::read_dir(&config.dir)
+        .map(|x| {
+            x.filter_map(|x| x.ok())
+                .map(|x| x.path())
+                .filter(|x| {
+                    let name = x.file_name().unwrap().to_string_lossy();
+                    name.starts_with(PREFIX) && name.ends_with(EXTENSION)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    snapshots.sort();
+    snapshots
+}
+
+#[cfg(unix)]
+fn write_archive(path: &Path, dirs: &[&str]) {
+    let file = File::create(path).expect("创建备份文件失败");
+    let mut builder = Builder::new(GzEncoder::new(file, Compression::default()));
+    for dir in dirs {
+        builder
+            .append_dir_all(dir, dir)
+            .unwrap_or_else(|e| panic!("备份 {} 失败，{}", dir, e));
+    }
+    builder
+        .into_inner()
+        .and_then(|x| x.finish())
+        .expect("写入备份文件失败");
+}
+
+#[cfg(windows)]
+fn write_archive(path: &Path, dirs: &[&str]) {
+    fn walk(dir: &Path, files: &mut Vec<PathBuf>) {
+        files.push(dir.to_owned());
+        for entry in
+            fs::read_dir(dir).unwrap_or_else(|e| panic!("读取 {} 失败，{}", dir.display(), e))
+        {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                walk(&path, files);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    let file = File::create(path).expect("创建备份文件失败");
+    let mut writer = ZipWriter::new(file);
+    let options = FileOptions::default();
+    for dir in dirs {
+        let mut files = Vec::new();
+        walk(Path::new(dir), &mut files);
+        for path in files {
+            //zip 中的路径统一用 /
+            let name: Vec<_> = path
+                .components()
+                .map(|x| x.as_os_str().to_string_lossy())
+                .collect();
+            let name = name.join("/");
+            if path.is_dir() {
+                writer
+                    .add_directory(name, options)
+                    .expect("写入备份文件失败");
+            } else {
+                writer.start_file(name, options).expect("写入备份文件失败");
+                let mut file = File::open(&path)
+                    .unwrap_or_else(|e| panic!("读取 {} 失败，{}", path.display(), e));
+                io::copy(&mut file, &mut writer).expect("写入备份文件失败");
+            }
+        }
+    }
+    writer.finish().expect("写入备份文件失败");
+}
+
+//打包现有的 config、data、bots，返回备份文件
+fn snapshot(config: &Backup) -> Option<PathBuf> {
+    let dirs: Vec<_> = BACKUP_DIRS
+        .iter()
+        .copied()
+        .filter(|x| Path::new(x).is_dir())
+        .collect();
+    if dirs.is_empty() {
+        info!("没有需要备份的目录");
+        return None;
+    }
+
+    utils::ensure_dir(&config.dir);
+    let name = format!("{}{}", PREFIX, timestamp());
+    let mut path = Path::new(&config.dir).join(format!("{}{}", name, EXTENSION));
+    //同一秒内多次备份时加上序号，_ 排在 . 之后，按文件名排序依然是先后顺序
+    let mut n = 1;
+    while path.exists() {
+        path = Path::new(&config.dir).join(format!("{}_{}{}", name, n, EXTENSION));
+        n += 1;
+    }
+    info!("备份 {} 到 {}", dirs.join(" "), path.display());
+    write_archive(&path, &dirs);
+    Some(path)
+}
+
+//备份后超出 keep 份时删除最旧的
+pub fn create(config: &Backup) -> Option<PathBuf> {
+    let path = snapshot(config)?;
+    let snapshots = snapshots(config);
+    if config.keep > 0 && snapshots.len() > config.keep {
+        for old in &snapshots[..snapshots.len() - config.keep] {
+            match fs::remove_file(old) {
+                Ok(()) => info!("删除旧的备份 {}", old.display()),
+                Err(e) => warn!("删除 {} 失败，{}", old.display(), e),
+            }
+        }
+    }
+    Some(path)
+}
+
+//启动前比较 mirai 与插件的版本，和上次启动时不同则先备份
+pub fn on_version_change(config: &Config) {
+    let versions: BTreeSet<String> = config
+        .mirai
+        .full
+        .iter()
+        .chain(config.mirai.maven.iter())
+        .map(|(project, version)| format!("{}:{}", project, version))
+        .chain(
+            config
+                .mirai
+                .plugins
+                .iter()
+                .map(|(name, plugin)| plugin.identity(name)),
+        )
+        .collect();
+    let versions: Vec<_> = versions.into_iter().collect();
+    let versions = versions.join("\n");
+
+    let versions_path = Path::new(&config.backup.dir).join(VERSIONS_NAME);
+    match fs::read_to_string(&versions_path) {
+        Ok(last) if last == versions => return,
+        //第一次启动时没有可以保护的数据，只记录版本
+        Err(_) => {}
+        Ok(_) if config.backup.auto => {
+            info!("mirai 或插件的版本有变化，启动前先备份");
+            create(&config.backup);
+        }
+        Ok(_) => {}
+    }
+    utils::ensure_dir(&config.backup.dir);
+    fs::write(versions_path, versions).expect("写入文件失败");
+}
+
+//先解压到当前目录下的临时目录，压缩包完好才替换现有的目录
+//临时目录与当前目录在同一文件系统，rename 不会跨盘复制
+fn restore(config: &Backup, target: &Path) {
+    info!("从 {} 恢复", target.display());
+    let temp_dir = tempfile::Builder::new()
+        .prefix(".mirua-restore-")
+        .tempdir_in(".")
+        .expect("创建临时目录失败");
+    let restored: Vec<_> = match utils::extract(target, temp_dir.path()) {
+        Ok(()) => BACKUP_DIRS
+            .iter()
+            .copied()
+            .filter(|x| temp_dir.path().join(x).is_dir())
+            .collect(),
+        Err(e) => {
+            let _ = temp_dir.close();
+            fail(format!("{}，现有的数据没有改动", e));
+        }
+    };
+    if restored.is_empty() {
+        let _ = temp_dir.close();
+        fail(format!(
+            "{} 中没有 {}，现有的数据没有改动",
+            target.display(),
+            BACKUP_DIRS.join(" ")
+        ));
+    }
+
+    //恢复前先备份当前的数据，恢复错了还能找回来，这次不删除旧的备份
+    snapshot(config);
+    //备份中没有的目录同样移走，恢复后与备份时一致
+    let old = temp_dir.path().join("old");
+    let swap = || -> Result<(), String> {
+        fs::create_dir(&old).map_err(|e| format!("创建临时目录失败，{}", e))?;
+        for dir in BACKUP_DIRS.iter().filter(|x| Path::new(x).is_dir()) {
+            fs::rename(dir, old.join(dir)).map_err(|e| format!("移走 {} 失败，{}", dir, e))?;
+        }
+        for dir in &restored {
+            fs::rename(temp_dir.path().join(dir), dir)
+                .map_err(|e| format!("恢复 {} 失败，{}", dir, e))?;
+        }
+        Ok(())
+    };
+    if let Err(e) = swap() {
+        //把移走的目录放回去，放不回去的留在临时目录中，不会随临时目录删除
+        for dir in BACKUP_DIRS.iter().filter(|x| old.join(x).is_dir()) {
+            let _ = fs::remove_dir_all(dir);
+            if let Err(e) = fs::rename(old.join(dir), dir) {
+                warn!(
+                    "放回 {} 失败，原来的目录在 {}，{}",
+                    dir,
+                    old.join(dir).display(),
+                    e
+                );
+            }
+        }
+        fail(format!("{}，已尝试还原原来的目录", e));
+    }
+    info!("恢复完成");
+}
+
+//mirua backup create|list|restore <备份>|latest
+pub fn command(config: &Config, args: &[String]) {
+    let config = &config.backup;
+    match args.first().map(String::as_str) {
+        Some("create") => {
+            create(config);
+        }
+        None | Some("list") => {
+            let snapshots = snapshots(config);
+            if snapshots.is_empty() {
+                info!("{} 中还没有备份", config.dir);
+                return;
+            }
+            let rows: Vec<[String; 2]> = snapshots
+                .iter()
+                .map(|x| {
+                    let size = x.metadata().map(|x| x.len()).unwrap_or(0) as f64 / 1024.0;
+                    let size = if size < 1024.0 {
+                        format!("{:.1} KB", size)
+                    } else {
+                        format!("{:.1} MB", size / 1024.0)
+                    };
+                    [x.file_name().unwrap().to_string_lossy().into_owned(), size]
+                })
+                .collect();
+            let rows: Vec<[&str; 2]> = rows.iter().map(|[a, b]| [a.as_str(), b.as_str()]).collect();
+            utils::print_table(["备份", "大小"], &rows);
+        }
+        Some("restore") => {
+            let snapshots = snapshots(config);
+            let target = match args.get(1).map(String::as_str) {
+                None => fail("需要指定备份，可以用 latest 表示最新的备份".to_owned()),
+                Some("latest") => snapshots.last().cloned(),
+                Some(name) => snapshots
+                    .iter()
+                    .find(|x| x.file_name().unwrap().to_string_lossy() == name)
+                    .cloned(),
+            }
+            .unwrap_or_else(|| {
+                fail(format!(
+                    "找不到备份，只能恢复 {} 中的备份，用 mirua backup list 查看已有的备份",
+                    config.dir
+                ))
+            });
+            restore(config, &target);
+        }
+        Some(x) => fail(format!(
+            "未知命令 backup {}，可用命令：create list restore",
+            x
+        )),
+    }
+}
@@ -0,0 +1,55 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use log::{debug, info};
+
+use crate::{jre::discover, utils::fail, MIRAI_PATH};
+
+//CreateProcess 的命令行最长 32767 个字符，给 java 路径和其它参数留点余量
+const WINDOWS_COMMAND_LINE_LIMIT: usize = 32767 - 1024;
+const ARGFILE_NAME: &str = ".mirua-classpath";
+
+//按给定顺序拼接 classpath，使用平台自己的分隔符
+pub fn build(jars: &[PathBuf]) -> String {
+    env::join_paths(jars)
+        .expect("jar 路径中包含路径分隔符")
+        .into_string()
+        .expect("jar 路径不是合法的 UTF-8")
+}
+
+//设置 -cp 参数，命令行过长时改用 java 9 以上支持的 @argfile
+//java 8 会把 @argfile 当成主类，只能报错退出
+pub fn apply(command: &mut Command, java_path: &Path, jars: &[PathBuf]) {
+    let classpath = build(jars);
+    debug!("classpath: {}", classpath);
+
+    if !cfg!(windows) || classpath.len() <= WINDOWS_COMMAND_LINE_LIMIT {
+        command.args(["-cp", &classpath]);
+        return;
+    }
+
+    match discover::inspect(java_path) {
+        Some(x) if x.version.feature >= 9 => {}
+        Some(x) => fail(format!(
+            "classpath 长度 {} 超过了 Windows 命令行的限制，java {} 不支持 @argfile，请在 [jre] 中使用 9 以上的版本，或者减少插件",
+            classpath.len(),
+            x.version.feature
+        )),
+        None => fail(format!(
+            "classpath 长度 {} 超过了 Windows 命令行的限制，无法识别 {} 的版本，不能确定是否支持 @argfile",
+            classpath.len(),
+            java_path.display()
+        )),
+    }
+
+    //argfile 的引号内反斜杠是转义符
+    let argfile = Path::new(MIRAI_PATH).join(ARGFILE_NAME);
+    let escaped = classpath.replace('\\', "\\\\").replace('"', "\\\"");
+    fs::write(&argfile, format!("-cp\n\"{}\"\n", escaped)).expect("写入 argfile 失败");
+    info!("classpath 过长，改用 {}", argfile.display());
+
+    command.arg(format!("@{}", argfile.display()));
+}
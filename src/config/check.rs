@@ -0,0 +1,487 @@
+use std::{convert::TryFrom, ops::Range};
+
+use toml_edit::{ImDocument, Item, Key, TableLike, Value};
+
+//配置文件的结构，用于在反序列化之前逐项检查
+enum Kind {
+    Bool,
+    Integer,
+    //不小于 0，且不超过 u32 的范围
+    Unsigned,
+    String,
+    //只能取其中之一的字符串
+    Choice(&'static [&'static str]),
+    Strings,
+    Table(&'static [Field]),
+    //键为 "<groupId>:<artifactId>"，值为版本号
+    Coordinates,
+    //同 Coordinates，另外允许值为内联表，从 url、github 或本地路径获取
+    Plugins,
+    //键为 QQ 号，值为账号配置
+    Bots,
+}
+
+struct Field {
+    name: &'static str,
+    kind: Kind,
+}
+
+const fn field(name: &'static str, kind: Kind) -> Field {
+    Field { name, kind }
+}
+
+const UPDATE: [Field; 6] = [
+    field("enabled", Kind::Bool),
+    field("channel", Kind::Choice(&["stable", "beta"])),
+    field("pin", Kind::String),
+    field("max-version", Kind::String),
+    field("manifest", Kind::String),
+    field("public-key", Kind::String),
+];
+
+const BACKUP: [Field; 3] = [
+    field("auto", Kind::Bool),
+    field("keep", Kind::Unsigned),
+    field("dir", Kind::String),
+];
+
+const JRE: [Field; 9] = [
+    field("path", Kind::String),
+    field("arch", Kind::String),
+    field("version", Kind::Unsigned),
+    field(
+        "vendor",
+        Kind::Choice(&[
+            "temurin",
+            "adoptium",
+            "semeru",
+            "openj9",
+            "zulu",
+            "dragonwell",
+        ]),
+    ),
+    field("impl", Kind::Choice(&["hotspot", "openj9"])),
+    field("discover", Kind::Bool),
+    field("store", Kind::String),
+    field("mirror", Kind::String),
+    field("url-template", Kind::String),
+];
+
+const MIRAI: [Field; 3] = [
+    field("full", Kind::Coordinates),
+    field("maven", Kind::Coordinates),
+    field("plugins", Kind::Plugins),
+];
+
+//插件来源，按各自的第一个键区分
+const PLUGIN_SOURCES: [&[Field]; 3] = [
+    &[field("url", Kind::String), field("sha256", Kind::String)],
+    &[
+        field("github", Kind::String),
+        field("asset", Kind::String),
+        field("version", Kind::String),
+        field("sha256", Kind::String),
+    ],
+    &[field("path", Kind::String)],
+];
+
+const BOT: [Field; 7] = [
+    field(
+        "protocol",
+        Kind::Choice(&[
+            "android_phone",
+            "android_pad",
+            "android_watch",
+            "ipad",
+            "macos",
+        ]),
+    ),
+    field("password-env", Kind::String),
+    field("password-file", Kind::String),
+    field("password-kind", Kind::Choice(&["plain", "md5"])),
+    field("device", Kind::String),
+    field("enable", Kind::Bool),
+    field(
+        "heartbeat-strategy",
+        Kind::Choice(&["stat_hb", "register", "none"]),
+    ),
+];
+
+const ROOT: [Field; 9] = [
+    field("config-version", Kind::Integer),
+    field("update", Kind::Table(&UPDATE)),
+    field("bootstrap-commands", Kind::Strings),
+    field("entrypoint", Kind::String),
+    field(
+        "plugin-compatibility",
+        Kind::Choice(&["warn", "block", "off"]),
+    ),
+    field("backup", Kind::Table(&BACKUP)),
+    field("jre", Kind::Table(&JRE)),
+    field("mirai", Kind::Table(&MIRAI)),
+    field("bots", Kind::Bots),
+];
+
+#[derive(Debug)]
+pub struct Problem {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+struct Checker<'a> {
+    buf: &'a str,
+    problems: Vec<(usize, String)>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, span: Option<Range<usize>>, message: String) {
+        self.problems
+            .push((span.map(|x| x.start).unwrap_or(0), message));
+    }
+
+    fn check_table(&mut self, path: &str, table: &dyn TableLike, fields: &[Field]) {
+        for (name, _) in table.iter() {
+            let (key, item) = table.get_key_value(name).unwrap();
+            match fields.iter().find(|x| x.name == name) {
+                Some(field) => self.check_item(&format!("{}{}", path, name), item, &field.kind),
+                None => {
+                    let suggestion = suggest(name, fields.iter().map(|x| x.name))
+                        .map(|x| format!("，是不是 {}{}？", path, x))
+                        .unwrap_or_default();
+                    self.report(
+                        key.span(),
+                        format!("未知的键 {}{}{}", path, name, suggestion),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_item(&mut self, path: &str, item: &Item, kind: &Kind) {
+        let span = item.span();
+        let ok = match (kind, item) {
+            (Kind::Bool, Item::Value(Value::Boolean(_))) => true,
+            (Kind::Integer, Item::Value(Value::Integer(_))) => true,
+            (Kind::Unsigned, Item::Value(Value::Integer(x))) => {
+                if u32::try_from(*x.value()).is_err() {
+                    self.report(
+                        span.clone(),
+                        format!(
+                            "{} 的值 {} 超出范围，应在 0 到 {} 之间",
+                            path,
+                            x.value(),
+                            u32::MAX
+                        ),
+                    );
+                }
+                true
+            }
+            (Kind::String, Item::Value(Value::String(_))) => true,
+            (Kind::Choice(choices), Item::Value(Value::String(x))) => {
+                if !choices.contains(&x.value().to_lowercase().as_str()) {
+                    self.report(
+                        span.clone(),
+                        format!(
+                            "{} 的值 \"{}\" 无效，可选 {}",
+                            path,
+                            x.value(),
+                            choices.join(" ")
+                        ),
+                    );
+                }
+                true
+            }
+            (Kind::Strings, Item::Value(Value::Array(array))) => {
+                for value in array.iter().filter(|x| !x.is_str()) {
+                    self.report(
+                        value.span(),
+                        format!("{} 中的元素应为字符串，实际为{}", path, value_name(value)),
+                    );
+                }
+                true
+            }
+            (Kind::Table(fields), _) if item.is_table_like() => {
+                let path = format!("{}.", path);
+                self.check_table(&path, item.as_table_like().unwrap(), fields);
+                true
+            }
+            (Kind::Coordinates, _) if item.is_table_like() => {
+                self.check_coordinates(path, item.as_table_like().unwrap(), false);
+                true
+            }
+            (Kind::Plugins, _) if item.is_table_like() => {
+                self.check_coordinates(path, item.as_table_like().unwrap(), true);
+                true
+            }
+            (Kind::Bots, _) if item.is_table_like() => {
+                self.check_bots(path, item.as_table_like().unwrap());
+                true
+            }
+            _ => false,
+        };
+        if !ok {
+            self.report(
+                span,
+                format!(
+                    "{} 应为{}，实际为{}",
+                    path,
+                    kind_name(kind),
+                    item_name(item)
+                ),
+            );
+        }
+    }
+
+    fn check_coordinates(&mut self, path: &str, table: &dyn TableLike, plugins: bool) {
+        for (name, _) in table.iter() {
+            let (key, item) = table.get_key_value(name).unwrap();
+            if plugins && item.is_table_like() {
+                self.check_plugin_source(path, name, item);
+                continue;
+            }
+            if parse_coordinate(name).is_none() {
+                self.report(
+                    key.span(),
+                    format!(
+                        "{} 中的 \"{}\" 不是合法的坐标，格式为 \"<groupId>:<artifactId>\"",
+                        path, name
+                    ),
+                );
+            }
+            match item.as_str() {
+                Some(version) if is_version(version) => {}
+                Some(version) => self.report(
+                    item.span(),
+                    format!("{} 中 \"{}\" 的版本号 \"{}\" 无效", path, name, version),
+                ),
+                None => self.report(
+                    item.span(),
+                    format!(
+                        "{} 中 \"{}\" 的版本号应为字符串，实际为{}",
+                        path,
+                        name,
+                        item_name(item)
+                    ),
+                ),
+            }
+        }
+    }
+
+    fn check_bots(&mut self, path: &str, table: &dyn TableLike) {
+        for (name, _) in table.iter() {
+            let (key, item) = table.get_key_value(name).unwrap();
+            let path = format!("{}.{}", path, Key::new(name));
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_digit()) {
+                self.report(key.span(), format!("{} 的键应为 QQ 号", path));
+            }
+            self.check_item(&path, item, &Kind::Table(&BOT));
+            let bot = match item.as_table_like() {
+                Some(x) => x,
+                None => continue,
+            };
+            if !bot.contains_key("password-env") && !bot.contains_key("password-file") {
+                self.report(
+                    item.span(),
+                    format!("{} 需要用 password-env 或 password-file 指定密码", path),
+                );
+            }
+            //MIRUA_CFG_ 开头的环境变量会被当作配置项
+            if let Some((key, value)) = bot.get_key_value("password-env") {
+                if value
+                    .as_str()
+                    .is_some_and(|x| x.to_uppercase().starts_with("MIRUA_CFG_"))
+                {
+                    self.report(
+                        key.span(),
+                        format!(
+                            "{}.password-env 不能以 MIRUA_CFG_ 开头，这类环境变量用于覆盖配置",
+                            path
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_plugin_source(&mut self, path: &str, name: &str, item: &Item) {
+        let table = item.as_table_like().unwrap();
+        let sources: Vec<_> = PLUGIN_SOURCES
+            .iter()
+            .filter(|x| table.contains_key(x[0].name))
+            .collect();
+        match sources.as_slice() {
+            [fields] => self.check_table(&format!("{}.\"{}\".", path, name), table, fields),
+            [] => self.report(
+                item.span(),
+                format!(
+                    "{} 中的 \"{}\" 需要指定来源 url、github 或 path",
+                    path, name
+                ),
+            ),
+            _ => self.report(
+                item.span(),
+                format!(
+                    "{} 中的 \"{}\" 只能指定 url、github、path 中的一个",
+                    path, name
+                ),
+            ),
+        }
+    }
+
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let before = &self.buf[..offset.min(self.buf.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap().chars().count() + 1;
+        (line, column)
+    }
+}
+
+fn is_name(x: &str) -> bool {
+    !x.is_empty()
+        && x.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_')
+}
+
+//"net.mamoe:mirai-console" -> ("net.mamoe", "mirai-console")
+pub fn parse_coordinate(project: &str) -> Option<(&str, &str)> {
+    let mut parts = project.split(':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(group_id), Some(artifact_id), None) if is_name(group_id) && is_name(artifact_id) => {
+            Some((group_id, artifact_id))
+        }
+        _ => None,
+    }
+}
+
+fn is_version(version: &str) -> bool {
+    is_name(version) && version.starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn kind_name(kind: &Kind) -> &'static str {
+    match kind {
+        Kind::Bool => "布尔值",
+        Kind::Integer => "整数",
+        Kind::Unsigned => "非负整数",
+        Kind::String | Kind::Choice(_) => "字符串",
+        Kind::Strings => "字符串数组",
+        Kind::Table(_) | Kind::Coordinates | Kind::Plugins | Kind::Bots => "表",
+    }
+}
+
+fn value_name(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "字符串",
+        Value::Integer(_) => "整数",
+        Value::Float(_) => "浮点数",
+        Value::Boolean(_) => "布尔值",
+        Value::Datetime(_) => "日期时间",
+        Value::Array(_) => "数组",
+        Value::InlineTable(_) => "表",
+    }
+}
+
+fn item_name(item: &Item) -> &'static str {
+    match item {
+        Item::None => "空",
+        Item::Value(x) => value_name(x),
+        Item::Table(_) => "表",
+        Item::ArrayOfTables(_) => "表数组",
+    }
+}
+
+//编辑距离，相邻字符交换算一次
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+//拼写相近的候选，允许的差异随长度增加，最多 3 个字符
+fn suggest<'a, I: Iterator<Item = &'a str>>(name: &str, candidates: I) -> Option<&'a str> {
+    let name = name.to_lowercase().replace('_', "-");
+    candidates
+        .map(|x| (edit_distance(&name, x), x))
+        .filter(|(distance, x)| *distance <= (x.len() / 3).clamp(1, 3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, x)| x)
+}
+
+//路径对应的值在配置文件中的行列，找不到时返回 None
+pub fn locate(buf: &str, path: &[String]) -> Option<(usize, usize)> {
+    let doc = ImDocument::parse(buf).ok()?;
+    let mut item = doc.as_item();
+    for key in path {
+        item = item.get(key.as_str())?;
+    }
+    let checker = Checker {
+        buf,
+        problems: Vec::new(),
+    };
+    Some(checker.position(item.span()?.start))
+}
+
+//检查配置文件内容，返回按位置排序的全部问题
+pub fn check(buf: &str) -> Vec<Problem> {
+    let mut checker = Checker {
+        buf,
+        problems: Vec::new(),
+    };
+
+    match ImDocument::parse(buf) {
+        Ok(doc) => checker.check_table("", doc.as_table(), &ROOT),
+        Err(e) => checker.report(
+            e.span(),
+            format!("TOML 语法错误，{}", e.message().trim().replace('\n', "，")),
+        ),
+    }
+
+    checker.problems.sort_by_key(|(offset, _)| *offset);
+    checker
+        .problems
+        .iter()
+        .map(|(offset, message)| {
+            let (line, column) = checker.position(*offset);
+            Problem {
+                line,
+                column,
+                message: message.to_owned(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_integers() {
+        let problems = check("[backup]\nkeep = -1\n\n[jre]\nversion = -3\n");
+        let locations: Vec<_> = problems.iter().map(|x| (x.line, x.column)).collect();
+        assert_eq!(locations, [(2, 8), (5, 11)]);
+        assert!(check("[backup]\nkeep = 0\n\n[jre]\nversion = 17\n").is_empty());
+    }
+
+    #[test]
+    fn locate_value() {
+        let buf = "[mirai.plugins]\n\"org.itxtech:mirai-native\" = \"2.0.0\"\n";
+        let path = ["mirai", "plugins", "org.itxtech:mirai-native"].map(String::from);
+        assert_eq!(locate(buf, &path), Some((2, 30)));
+    }
+}
@@ -0,0 +1,335 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use log::{debug, error, info};
+use toml::{value::Table, Value};
+use toml_edit::Key;
+
+use super::{check, migrate, read_config, report, TEMPLATE};
+
+//配置按以下顺序叠加，后面的覆盖前面的：
+//内置模板 -> /etc/mirua/config.toml -> 用户目录下的 mirua/config.toml -> ./mirua.toml
+//-> MIRUA_CFG_* 环境变量 -> 命令行 --set key=value
+pub struct Layered {
+    pub value: Value,
+    //每个值的来源，键为值所在的路径
+    pub origins: BTreeMap<Vec<String>, String>,
+}
+
+fn global_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if cfg!(unix) {
+        paths.push(PathBuf::from("/etc/mirua/config.toml"));
+    }
+    let user_dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|x| Path::new(&x).join(".config")))
+    };
+    if let Some(user_dir) = user_dir {
+        paths.push(user_dir.join("mirua").join("config.toml"));
+    }
+    paths
+}
+
+//[mirai.*] 是项目列表，配置文件中出现时整体替换下层的，否则删不掉默认的项目
+fn is_project_list(path: &[String]) -> bool {
+    path.len() == 2 && path[0] == "mirai"
+}
+
+fn record(
+    value: &Value,
+    path: &mut Vec<String>,
+    origin: &str,
+    origins: &mut BTreeMap<Vec<String>, String>,
+) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table {
+                path.push(key.to_owned());
+                record(value, path, origin, origins);
+                path.pop();
+            }
+        }
+        _ => {
+            origins.insert(path.to_owned(), origin.to_owned());
+        }
+    }
+}
+
+fn merge(
+    base: &mut Table,
+    overlay: Table,
+    path: &mut Vec<String>,
+    origin: &str,
+    origins: &mut BTreeMap<Vec<String>, String>,
+    replace_lists: bool,
+) {
+    for (key, value) in overlay {
+        path.push(key.to_owned());
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay))
+                if !(replace_lists && is_project_list(path)) =>
+            {
+                merge(base, overlay, path, origin, origins, replace_lists);
+            }
+            (_, value) => {
+                let prefix = path.to_owned();
+                origins.retain(|x, _| !x.starts_with(&prefix));
+                record(&value, path, origin, origins);
+                base.insert(key, value);
+            }
+        }
+        path.pop();
+    }
+}
+
+impl Layered {
+    //值的来源，来自文件时带上行列，如 "./mirua.toml:3:10"
+    pub fn locate(&self, path: &[String]) -> String {
+        let origin = match self.origins.get(path) {
+            Some(x) => x,
+            None => return "配置".to_owned(),
+        };
+        match fs::read_to_string(origin)
+            .ok()
+            .and_then(|buf| check::locate(&buf, path))
+        {
+            Some((line, column)) => format!("{}:{}:{}", origin, line, column),
+            None => origin.to_owned(),
+        }
+    }
+
+    fn apply(&mut self, overlay: Value, origin: &str, replace_lists: bool) {
+        if let (Value::Table(base), Value::Table(overlay)) = (&mut self.value, overlay) {
+            merge(
+                base,
+                overlay,
+                &mut Vec::new(),
+                origin,
+                &mut self.origins,
+                replace_lists,
+            );
+        }
+    }
+}
+
+//"jre.version" -> 含一个值的 TOML 文档，值无法解析时当作字符串
+//配置中没有小数，2.0 之类的也当作字符串，多半是版本号
+fn single_value(path: &[String], value: &str) -> String {
+    let key: Vec<_> = path
+        .iter()
+        .map(|x| Key::new(x.as_str()).to_string())
+        .collect();
+    let value = match format!("v = {}", value).parse::<toml_edit::DocumentMut>() {
+        Ok(doc) if !doc["v"].is_float() => value.to_owned(),
+        _ => toml_edit::Value::from(value).to_string(),
+    };
+    format!("{} = {}", key.join("."), value.trim())
+}
+
+//只认 MIRUA_CFG_ 开头的变量，其它 MIRUA_ 变量可能另有用途
+const ENV_PREFIX: &str = "MIRUA_CFG_";
+
+//MIRUA_CFG_JRE__URL_TEMPLATE=... -> jre.url-template，层级用两个下划线分隔
+fn env_overrides() -> Vec<(String, Vec<String>, String)> {
+    let mut overrides: Vec<_> = env::vars_os()
+        .filter_map(|(name, value)| {
+            let (name, value) = (name.into_string().ok()?, value.into_string().ok()?);
+            let path = name
+                .strip_prefix(ENV_PREFIX)?
+                .split("__")
+                .map(|x| x.to_lowercase().replace('_', "-"))
+                .collect();
+            Some((format!("环境变量 {}", name), path, value))
+        })
+        .collect();
+    overrides.sort();
+    overrides
+}
+
+//--set 'mirai.plugins."org.itxtech:mirai-native"=2.0.0'，键可以带引号
+fn parse_set(arg: &str) -> Option<(Vec<String>, String)> {
+    let mut quoted = false;
+    let i = arg.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        '=' if !quoted => Some(i),
+        _ => None,
+    })?;
+    let path = Key::parse(arg[..i].trim())
+        .ok()?
+        .iter()
+        .map(|x| x.get().to_owned())
+        .collect();
+    Some((path, arg[i + 1..].trim().to_owned()))
+}
+
+//读取所有层级，有问题时逐条报告后退出
+//dry_run 时本地配置文件需要迁移也不写入，只报告迁移的内容
+pub fn load(config_path: &Path, sets: &[String], dry_run: bool) -> Layered {
+    let mut layered = Layered {
+        value: Value::Table(Table::new()),
+        origins: BTreeMap::new(),
+    };
+    layered.apply(
+        TEMPLATE.parse().expect("解析配置文件模板失败"),
+        "默认值",
+        false,
+    );
+
+    let mut ok = true;
+    let mut files: Vec<_> = global_paths()
+        .into_iter()
+        .filter(|x| x.is_file())
+        .map(|x| {
+            let buf = fs::read_to_string(&x).expect("打开文件失败");
+            (x.display().to_string(), buf)
+        })
+        .collect();
+    let name = config_path.display().to_string();
+    let buf = read_config(config_path);
+    match migrate::migrate(&buf) {
+        Ok(None) => files.push((name, buf)),
+        Ok(Some(migrated)) if !dry_run => {
+            migrate::apply(config_path, &migrated);
+            files.push((name, migrated.buf));
+        }
+        //检查的是迁移后的内容，行列与原文件不同
+        Ok(Some(migrated)) => {
+            info!(
+                "{} 为版本 {}，启动时会迁移到版本 {}，以下为迁移后的检查结果",
+                name,
+                migrated.from,
+                migrate::CONFIG_VERSION
+            );
+            for change in &migrated.changes {
+                info!("{}", change);
+            }
+            files.push((format!("{}（迁移后）", name), migrated.buf));
+        }
+        Err(problem) => {
+            error!(
+                "{}:{}:{}: {}",
+                name, problem.line, problem.column, problem.message
+            );
+            ok = false;
+        }
+    }
+    for (name, buf) in files {
+        debug!("读取配置文件 {}", name);
+        if !report(&name, &buf) {
+            ok = false;
+            continue;
+        }
+        let value = buf.parse().expect("解析配置文件失败");
+        layered.apply(value, &name, true);
+    }
+
+    let mut overrides = env_overrides();
+    for arg in sets {
+        match parse_set(arg) {
+            Some((path, value)) => overrides.push((format!("--set {}", arg), path, value)),
+            None => {
+                error!("无法解析 --set {}，格式为 key=value", arg);
+                ok = false;
+            }
+        }
+    }
+    for (origin, path, value) in overrides {
+        let buf = single_value(&path, &value);
+        let problems = check::check(&buf);
+        for problem in &problems {
+            error!("{}: {}", origin, problem.message);
+        }
+        if !problems.is_empty() {
+            ok = false;
+            continue;
+        }
+        layered.apply(buf.parse().expect("解析配置失败"), &origin, false);
+    }
+
+    if !check_layered(&layered) {
+        ok = false;
+    }
+    if !ok {
+        process::exit(1);
+    }
+    layered
+}
+
+//各项可能来自不同的层，互相关联的项要在叠加后检查
+fn check_layered(layered: &Layered) -> bool {
+    let jre = layered.value.get("jre");
+    let has = |key: &str| jre.and_then(|x| x.get(key)).is_some();
+    if has("mirror") && !has("url-template") {
+        let path = ["jre".to_owned(), "mirror".to_owned()];
+        error!(
+            "{}: jre.mirror 需要与 jre.url-template 一起设置，只设置 mirror 时仍会从发行版的接口下载",
+            layered.locate(&path)
+        );
+        return false;
+    }
+    true
+}
+
+fn display_value(value: &Value) -> String {
+    let mut table = Table::new();
+    table.insert("v".to_owned(), value.clone());
+    toml::to_string(&table)
+        .expect("输出配置失败")
+        .trim_start_matches("v = ")
+        .trim_end()
+        .to_owned()
+}
+
+fn print_table(
+    table: &Table,
+    path: &mut Vec<String>,
+    origins: Option<&BTreeMap<Vec<String>, String>>,
+) {
+    let values: Vec<_> = table.iter().filter(|(_, x)| !x.is_table()).collect();
+    //只有子表的表不输出表头
+    if !path.is_empty() && (!values.is_empty() || table.is_empty()) {
+        let header: Vec<_> = path
+            .iter()
+            .map(|x| Key::new(x.as_str()).to_string())
+            .collect();
+        println!("\n[{}]", header.join("."));
+    }
+    for (key, value) in values {
+        let line = format!("{} = {}", Key::new(key.as_str()), display_value(value));
+        path.push(key.to_owned());
+        match origins.and_then(|x| x.get(path.as_slice())) {
+            Some(origin) => println!("{:<60} # {}", line, origin),
+            None => println!("{}", line),
+        }
+        path.pop();
+    }
+    for (key, value) in table {
+        if let Value::Table(table) = value {
+            path.push(key.to_owned());
+            print_table(table, path, origins);
+            path.pop();
+        }
+    }
+}
+
+//mirua config show [--origin]
+pub fn show(layered: &Layered, origin: bool) {
+    if let Value::Table(table) = &layered.value {
+        print_table(
+            table,
+            &mut Vec::new(),
+            Some(&layered.origins).filter(|_| origin),
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::info;
+use toml_edit::{value, Decor, DocumentMut, Item, Key, Table};
+
+use super::{
+    check::{locate, Problem},
+    prefix_of, remove_entry,
+};
+
+//当前配置文件版本
+pub const CONFIG_VERSION: i64 = 3;
+
+//每一步迁移修改文档，返回改动的说明
+type Migration = fn(&mut DocumentMut) -> Vec<String>;
+
+//MIGRATIONS[i] 把版本 i + 2 迁移到 i + 3
+const MIGRATIONS: [Migration; 1] = [v2_to_v3];
+
+//把 key 放到表的最前面，其它键保持原来的顺序
+fn move_to_front(table: &mut Table, key: &str) {
+    table.sort_values_by(|a, _, b, _| (a.get() != key).cmp(&(b.get() != key)));
+}
+
+//新建的表默认排在文档最后，会跑到最后一个表的注释前面
+//放到 anchors 中第一个存在的表之前，都不存在时保持原样
+fn place_before(doc: &mut DocumentMut, key: &str, anchors: &[&str]) {
+    let anchor = match anchors
+        .iter()
+        .find(|x| doc.get(x).is_some_and(Item::is_table))
+    {
+        Some(x) => *x,
+        None => return,
+    };
+    let position = doc[anchor].as_table().unwrap().position();
+    if let (Some(position), Some(table)) = (position, doc[key].as_table_mut()) {
+        table.set_position(position);
+    }
+    //位置相同的表按键的顺序输出
+    let mut order: Vec<String> = doc
+        .iter()
+        .map(|(x, _)| x.to_owned())
+        .filter(|x| x != key)
+        .collect();
+    let i = order.iter().position(|x| x == anchor).unwrap();
+    order.insert(i, key.to_owned());
+    let rank = |x: &str| order.iter().position(|y| y == x);
+    doc.as_table_mut()
+        .sort_values_by(|a, _, b, _| rank(a.get()).cmp(&rank(b.get())));
+}
+
+//版本 2 -> 3
+//self-update 移入 [update] enabled
+//jre 改为按发行版与版本分目录存放后，指向旧 ./runtime/bin/java 的 jre.path 不再有效
+fn v2_to_v3(doc: &mut DocumentMut) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    //旧的文件头写着版本 2，换成 config-version 的说明
+    let mut header = String::new();
+    if let Some((key, item)) = doc.remove_entry("self-update") {
+        let prefix = prefix_of(key.leaf_decor());
+        let (head, comment) = match prefix.rfind("\n\n") {
+            Some(i) => prefix.split_at(i + 2),
+            None => ("", prefix.as_str()),
+        };
+        header = head
+            .lines()
+            .filter(|x| !x.contains("配置文件版本"))
+            .map(|x| format!("{}\n", x))
+            .collect();
+
+        if doc.get("update").is_none() {
+            doc["update"] = Item::Table(Table::new());
+            place_before(doc, "update", &["jre", "mirai"]);
+        }
+        let update = doc["update"].as_table_mut().expect("update 必须是表");
+        let enabled = Key::new("enabled").with_leaf_decor(Decor::new(comment, " "));
+        update.insert_formatted(&enabled, item);
+        move_to_front(update, "enabled");
+        changes.push("self-update 移动到 [update] enabled".to_owned());
+    }
+
+    let legacy_path = doc
+        .get("jre")
+        .and_then(|x| x.get("path"))
+        .and_then(|x| x.as_str())
+        .map(|x| x.trim_start_matches("./").replace('\\', "/"))
+        .filter(|x| x == "runtime/bin/java" || x == "runtime/bin/java.exe");
+    if legacy_path.is_some() {
+        remove_entry(doc, &["jre"], "path", true);
+        changes.push("注释掉 jre.path，./runtime 下的 jre 改由 mirua 管理".to_owned());
+    }
+
+    let comment = "# 配置文件格式版本，mirua 会自动迁移旧版本的配置文件，请勿手动修改\n";
+    let header = header.trim_end_matches('\n');
+    let prefix = if header.is_empty() {
+        comment.to_owned()
+    } else {
+        format!("{}\n\n{}", header, comment)
+    };
+    let key = Key::new("config-version").with_leaf_decor(Decor::new(prefix, " "));
+    doc.insert_formatted(&key, value(3));
+    move_to_front(doc.as_table_mut(), "config-version");
+
+    changes
+}
+
+fn backup_path(config_path: &Path, version: i64) -> PathBuf {
+    let name = config_path.file_name().unwrap().to_string_lossy();
+    let mut path = config_path.with_file_name(format!("{}.v{}.bak", name, version));
+    let mut i = 1;
+    while path.exists() {
+        path = config_path.with_file_name(format!("{}.v{}.bak.{}", name, version, i));
+        i += 1;
+    }
+    path
+}
+
+//迁移后的配置文件，from 为原来的版本，changes 为逐项的改动说明
+pub struct Migrated {
+    pub from: i64,
+    pub buf: String,
+    pub changes: Vec<String>,
+}
+
+//版本无法迁移时指向 config-version 所在的行列
+fn version_problem(buf: &str, message: String) -> Problem {
+    let (line, column) = locate(buf, &["config-version".to_owned()]).unwrap_or((1, 1));
+    Problem {
+        line,
+        column,
+        message,
+    }
+}
+
+//按 config-version 依次执行迁移，只修改内存中的内容，不需要迁移时返回 None
+pub fn migrate(buf: &str) -> Result<Option<Migrated>, Problem> {
+    let mut doc = match buf.parse::<DocumentMut>() {
+        Ok(x) => x,
+        //格式错误留给后面的检查报告
+        Err(_) => return Ok(None),
+    };
+    //版本 2 必须有 self-update，都没有的是省略了 config-version 的新版本配置
+    let version = match doc.get("config-version") {
+        None if doc.contains_key("self-update") => 2,
+        None => CONFIG_VERSION,
+        Some(x) => x
+            .as_integer()
+            .ok_or_else(|| version_problem(buf, "config-version 必须是整数".to_owned()))?,
+    };
+    if version == CONFIG_VERSION {
+        return Ok(None);
+    }
+    if version > CONFIG_VERSION {
+        return Err(version_problem(
+            buf,
+            format!(
+                "配置文件版本 {} 比当前 mirua 支持的版本 {} 新，请更新 mirua",
+                version, CONFIG_VERSION
+            ),
+        ));
+    }
+    if version < 2 {
+        return Err(version_problem(
+            buf,
+            format!(
+                "不支持迁移版本 {} 的配置文件，请删除 ./mirua.toml 重新生成",
+                version
+            ),
+        ));
+    }
+
+    let mut changes = Vec::new();
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize - 2) {
+        for change in migration(&mut doc) {
+            changes.push(format!("迁移到版本 {}：{}", i + 3, change));
+        }
+    }
+    Ok(Some(Migrated {
+        from: version,
+        buf: doc.to_string(),
+        changes,
+    }))
+}
+
+//备份原文件后写入迁移后的内容
+pub fn apply(config_path: &Path, migrated: &Migrated) {
+    let backup = backup_path(config_path, migrated.from);
+    fs::copy(config_path, &backup).expect("备份配置文件失败");
+    info!(
+        "配置文件版本 {} 需要迁移到 {}，原文件已备份到 {}",
+        migrated.from,
+        CONFIG_VERSION,
+        backup.display()
+    );
+    for change in &migrated.changes {
+        info!("{}", change);
+    }
+    fs::write(config_path, &migrated.buf).expect("写入文件失败");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //testdata/v2.toml 为版本 2 时生成的 mirua.toml 模板
+    #[test]
+    fn migrate_v2_template() {
+        let mut doc = include_str!("testdata/v2.toml")
+            .parse::<DocumentMut>()
+            .unwrap();
+        let changes = v2_to_v3(&mut doc);
+        assert_eq!(changes, ["self-update 移动到 [update] enabled"]);
+        assert_eq!(doc.to_string(), include_str!("testdata/v3.toml"));
+    }
+
+    #[test]
+    fn migrate_legacy_jre_path() {
+        let mut doc = "self-update = false\n\n[jre]\npath = \"./runtime/bin/java\"\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+        let changes = v2_to_v3(&mut doc);
+        assert_eq!(changes.len(), 2);
+        assert!(doc.get("jre").and_then(|x| x.get("path")).is_none());
+        assert_eq!(doc["update"]["enabled"].as_bool(), Some(false));
+    }
+
+    #[test]
+    fn unsupported_versions() {
+        let problem = migrate("# 说明\nconfig-version = \"x\"\n").err().unwrap();
+        assert_eq!((problem.line, problem.column), (2, 18));
+        let problem = migrate("config-version = 99\n").err().unwrap();
+        assert!(problem.message.contains("请更新 mirua"));
+        assert!(migrate("config-version = 1\n").is_err());
+        assert!(migrate("config-version = 3\n").unwrap().is_none());
+        let migrated = migrate("self-update = true\n").unwrap().unwrap();
+        assert_eq!(migrated.from, 2);
+        assert_eq!(
+            migrated.changes,
+            ["迁移到版本 3：self-update 移动到 [update] enabled"]
+        );
+    }
+}
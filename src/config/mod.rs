@@ -0,0 +1,437 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{Read, Write},
+    path::Path,
+    process,
+};
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use toml_edit::{Decor, DocumentMut, Item, Table, Value};
+
+pub mod check;
+pub mod layer;
+pub mod migrate;
+
+//内置的配置文件模板，同时也是各项的默认值
+pub const TEMPLATE: &str = include_str!("../mirua.toml.template");
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Mirai {
+    pub full: HashMap<String, String>,
+    pub maven: HashMap<String, String>,
+    pub plugins: HashMap<String, Plugin>,
+}
+
+//[mirai.plugins] 中的一项，字符串为 maven 上的版本号，其它来源写成内联表
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Plugin {
+    Maven(String),
+    Url(UrlPlugin),
+    Github(GithubPlugin),
+    Path(PathPlugin),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct UrlPlugin {
+    pub url: String,
+    pub sha256: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct GithubPlugin {
+    //owner/repo
+    pub github: String,
+    //文件名的通配符，默认为 *.jar
+    pub asset: Option<String>,
+    //release 的 tag，默认为 latest
+    pub version: Option<String>,
+    pub sha256: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PathPlugin {
+    pub path: String,
+}
+
+impl Plugin {
+    //记入清单的标识，配置中的标识变了，旧文件就会被删除
+    //maven 插件沿用 group:artifact:version
+    pub fn identity(&self, name: &str) -> String {
+        match self {
+            Plugin::Maven(version) => format!("{}:{}", name, version),
+            Plugin::Url(x) => format!("{}@url:{}", name, x.url),
+            Plugin::Github(x) => format!(
+                "{}@github:{}:{}",
+                name,
+                x.github,
+                x.version.as_deref().unwrap_or("latest")
+            ),
+            Plugin::Path(x) => format!("{}@path:{}", name, x.path),
+        }
+    }
+}
+
+impl From<Plugin> for Value {
+    fn from(plugin: Plugin) -> Value {
+        let table = match plugin {
+            Plugin::Maven(version) => return Value::from(version),
+            Plugin::Url(x) => toml::to_string(&x),
+            Plugin::Github(x) => toml::to_string(&x),
+            Plugin::Path(x) => toml::to_string(&x),
+        };
+        let table = table
+            .expect("生成配置失败")
+            .parse::<DocumentMut>()
+            .expect("生成配置失败");
+        Value::InlineTable(table.as_table().clone().into_inline_table())
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct JRE {
+    pub path: Option<String>,
+    pub arch: Option<String>,
+    pub version: Option<u32>,
+    pub vendor: Option<String>,
+    #[serde(rename = "impl")]
+    pub implementation: Option<String>,
+    pub discover: Option<bool>,
+    pub store: Option<String>,
+    pub mirror: Option<String>,
+    #[serde(rename = "url-template")]
+    pub url_template: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Update {
+    pub enabled: bool,
+    pub channel: Option<String>,
+    pub pin: Option<String>,
+    #[serde(rename = "max-version")]
+    pub max_version: Option<String>,
+    pub manifest: Option<String>,
+    #[serde(rename = "public-key")]
+    pub public_key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Backup {
+    pub auto: bool,
+    //0 表示不删除旧的备份
+    pub keep: usize,
+    pub dir: String,
+}
+
+//[bots.<QQ号>]，用于生成 mirai-console 的自动登录配置
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Bot {
+    pub protocol: Option<String>,
+    //从环境变量或文件读取密码，密码不写进 mirua.toml
+    #[serde(rename = "password-env")]
+    pub password_env: Option<String>,
+    #[serde(rename = "password-file")]
+    pub password_file: Option<String>,
+    #[serde(rename = "password-kind")]
+    pub password_kind: Option<String>,
+    //复制到 bots/<QQ号>/device.json 的设备信息
+    pub device: Option<String>,
+    pub enable: Option<bool>,
+    #[serde(rename = "heartbeat-strategy")]
+    pub heartbeat_strategy: Option<String>,
+}
+
+//除了 [mirai.*] 中的项目，所有项都可以省略，layer::load 以模板为最底层，省略的项使用模板中的值
+//模板中没有的项才在这里设置默认值
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Config {
+    #[serde(rename = "config-version")]
+    pub config_version: i64,
+    pub update: Update,
+    #[serde(rename = "bootstrap-commands")]
+    pub bootstrap_commands: Vec<String>,
+    #[serde(default)]
+    pub jre: JRE,
+    pub entrypoint: String,
+    #[serde(rename = "plugin-compatibility")]
+    pub plugin_compatibility: String,
+    pub backup: Backup,
+    pub mirai: Mirai,
+    #[serde(default)]
+    pub bots: BTreeMap<String, Bot>,
+}
+
+impl Config {
+    //叠加各层配置，本地配置文件不存在则创建，有问题时逐条报告后退出
+    //sets 为命令行中 --set 的参数
+    pub fn get_config(config_path: &str, sets: &[String]) -> Config {
+        let layered = layer::load(Path::new(config_path), sets, false);
+
+        match layered.value.clone().try_into() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("{}", describe_error(&layered, &e));
+                process::exit(1);
+            }
+        }
+    }
+}
+
+//check 没有发现的问题在反序列化时才报错，错误中只有键，按键找到值的来源和行列
+fn describe_error(layered: &layer::Layered, e: &toml::de::Error) -> String {
+    let message = e.to_string();
+    let (message, key) = match message.rsplit_once(" for key `") {
+        Some((message, key)) => (message, key.trim_end_matches('`')),
+        None => {
+            return format!(
+                "解析配置失败，{}，请检查配置文件格式与版本，或者可以选择直接删除./mirua.toml重来",
+                message
+            )
+        }
+    };
+    //错误中的键直接用 . 连接，插件坐标中也有 .，按来源记录的路径比对
+    let found = layered.origins.keys().find(|path| {
+        let joined = path.join(".");
+        joined == key || joined.starts_with(&format!("{}.", key))
+    });
+    match found {
+        Some(path) => format!("{}: {} 的值无效，{}", layered.locate(path), key, message),
+        None => format!("{} 的值无效，{}", key, message),
+    }
+}
+
+//读取配置文件，不存在则生成默认配置后退出
+fn read_config(config_path: &Path) -> String {
+    let buf = if config_path.exists() && config_path.is_file() {
+        let mut buf = String::new();
+
+        let mut f = File::open(config_path).expect("打开文件失败");
+        f.read_to_string(&mut buf).expect("打开文件失败");
+
+        buf
+    } else {
+        let buf = TEMPLATE.to_owned();
+
+        let mut f = File::create(config_path).expect("写入文件失败");
+        f.write_all(buf.as_bytes()).expect("写入文件失败");
+
+        info!("默认配置文件已经在当前目录生成，请确认后再次运行本程序");
+        info!("也可以使用 mirua init 按预设生成，或者加上 --accept-defaults 直接启动");
+        process::exit(0);
+    };
+
+    buf
+}
+
+//输出配置文件中的全部问题，没有问题时返回 true
+fn report(name: &str, buf: &str) -> bool {
+    let problems = check::check(buf);
+    for problem in &problems {
+        error!(
+            "{}:{}:{}: {}",
+            name, problem.line, problem.column, problem.message
+        );
+    }
+    problems.is_empty()
+}
+
+//mirua config check|show
+pub fn command(config_path: &str, sets: &[String], args: &[String]) {
+    match args.first().map(String::as_str) {
+        Some("check") => {
+            let config_path = Path::new(config_path);
+            if !config_path.is_file() {
+                error!("{} 不存在", config_path.display());
+                process::exit(1);
+            }
+            //各层都会检查，有问题时直接退出，需要迁移时只报告不写入
+            layer::load(config_path, sets, true);
+            info!("配置没有发现问题");
+        }
+        Some("show") => {
+            let layered = layer::load(Path::new(config_path), sets, true);
+            layer::show(&layered, args.iter().any(|x| x == "--origin"));
+        }
+        _ => {
+            error!("未知命令，可用命令：config check|show [--origin]");
+            process::exit(1);
+        }
+    }
+}
+
+//读取配置文件，修改后写回，toml_edit 会保留原有的注释与格式
+pub fn edit<F: FnOnce(&mut DocumentMut)>(config_path: &str, f: F) {
+    let buf = fs::read_to_string(config_path).expect("打开文件失败");
+    let mut doc = buf.parse::<DocumentMut>().expect("解析配置文件失败");
+    f(&mut doc);
+    fs::write(config_path, doc.to_string()).expect("写入文件失败");
+}
+
+//替换值时沿用原来值上的注释
+pub fn replace_value<V: Into<Value>>(item: &mut Item, value: V) {
+    let mut value = value.into();
+    match item.as_value() {
+        Some(old) => {
+            *value.decor_mut() = old.decor().clone();
+            *item = Item::Value(value);
+        }
+        None => *item = Item::Value(value),
+    }
+}
+
+//取出配置文件中的表，不存在时创建，上级表为隐式表，不单独输出表头
+pub fn table_mut<'a>(doc: &'a mut DocumentMut, path: &[&str]) -> &'a mut Table {
+    let mut table = doc.as_table_mut();
+    for (i, key) in path.iter().enumerate() {
+        let item = table.entry(key).or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(i + 1 < path.len());
+            Item::Table(table)
+        });
+        if let Some(inline) = item.as_inline_table() {
+            *item = Item::Table(inline.clone().into_table());
+        }
+        table = item
+            .as_table_mut()
+            .unwrap_or_else(|| panic!("配置文件中的 {} 必须是表", path[..=i].join(".")));
+    }
+    table
+}
+
+pub fn prefix_of(decor: &Decor) -> String {
+    decor
+        .prefix()
+        .and_then(|x| x.as_str())
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn table_positions(table: &Table, positions: &mut Vec<usize>) {
+    for (_, item) in table.iter() {
+        if let Some(table) = item.as_table() {
+            if let (false, Some(position)) = (table.is_implicit(), table.position()) {
+                positions.push(position);
+            }
+            table_positions(table, positions);
+        }
+    }
+}
+
+fn table_at(table: &mut Table, position: usize) -> Option<&mut Table> {
+    for (_, item) in table.iter_mut() {
+        if let Some(table) = item.as_table_mut() {
+            if !table.is_implicit() && table.position() == Some(position) {
+                return Some(table);
+            }
+            if let Some(table) = table_at(table, position) {
+                return Some(table);
+            }
+        }
+    }
+    None
+}
+
+//删除表中的键，原有的注释保留在原处，comment_out 为 true 时把键本身也改写为注释
+//注释挂在下一个键上，没有下一个键时挂在下一个表头上
+pub fn remove_entry(
+    doc: &mut DocumentMut,
+    path: &[&str],
+    key: &str,
+    comment_out: bool,
+) -> Option<Item> {
+    let mut table = doc.as_table_mut();
+    for x in path {
+        table = table.get_mut(x)?.as_table_mut()?;
+    }
+    let keys: Vec<String> = table.iter().map(|(k, _)| k.to_owned()).collect();
+    let next_key = keys
+        .iter()
+        .skip_while(|x| *x != key)
+        .nth(1)
+        .map(String::to_owned);
+    let position = table.position();
+    let (old_key, item) = table.remove_entry(key)?;
+    let mut comment = prefix_of(old_key.leaf_decor());
+    if comment_out {
+        comment += &format!("# {} = {}\n", old_key, item.to_string().trim());
+    }
+    if comment.is_empty() {
+        return Some(item);
+    }
+
+    if let Some(next_key) = next_key {
+        let mut next_key = table.key_mut(&next_key).unwrap();
+        let decor = next_key.leaf_decor_mut();
+        decor.set_prefix(comment + &prefix_of(decor));
+        return Some(item);
+    }
+
+    let mut positions = Vec::new();
+    table_positions(doc.as_table(), &mut positions);
+    let next_position = positions.into_iter().filter(|x| Some(*x) > position).min();
+    match next_position.and_then(|x| table_at(doc.as_table_mut(), x)) {
+        Some(next_table) => {
+            let decor = next_table.decor_mut();
+            decor.set_prefix(comment + &prefix_of(decor));
+        }
+        None => {
+            let trailing = doc.trailing().as_str().unwrap_or_default().to_owned();
+            doc.set_trailing(comment + &trailing);
+        }
+    }
+    Some(item)
+}
+
+//取出配置文件中的 [mirai.<section>]
+//配置文件中省略了该表时，先写入正在使用的值（默认值或其它配置文件中的），否则这些项目会丢失
+pub fn project_table<'a, V: Clone + Into<Value>>(
+    doc: &'a mut DocumentMut,
+    section: &str,
+    current: &HashMap<String, V>,
+) -> &'a mut Table {
+    let omitted = doc.get("mirai").and_then(|x| x.get(section)).is_none();
+    let table = table_mut(doc, &["mirai", section]);
+    if omitted {
+        let mut current: Vec<_> = current.iter().collect();
+        current.sort_by_key(|(project, _)| *project);
+        for (project, value) in current {
+            table.insert(project, toml_edit::value(value.clone()));
+        }
+    }
+    table
+}
+
+//改写配置文件 [mirai.<section>] 中的版本号
+pub fn set_versions<V: Clone + Into<Value>>(
+    config_path: &str,
+    section: &str,
+    current: &HashMap<String, V>,
+    updates: &[(String, String)],
+) {
+    edit(config_path, |doc| {
+        let table = project_table(doc, section, current);
+        for (project, version) in updates {
+            replace_value(table.entry(project).or_insert(Item::None), version.as_str());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Config 中没有默认值的项都要在模板中出现
+    #[test]
+    fn template_is_complete() {
+        let config: Config = toml::from_str(TEMPLATE).unwrap();
+        assert_eq!(config.config_version, migrate::CONFIG_VERSION);
+        assert!(config.update.enabled);
+        assert!(config.bots.is_empty());
+    }
+}
@@ -0,0 +1,38 @@
+use serde::Deserialize;
+
+use crate::utils::{get_checksum, get_json};
+
+const GITHUB_API: &str = "https://api.github.com/repos";
+
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+//tag 为 None 时取最新的 release
+pub fn release(repo: &str, tag: Option<&str>) -> Result<Release, String> {
+    let url = match tag {
+        Some(tag) => format!("{}/{}/releases/tags/{}", GITHUB_API, repo, tag),
+        None => format!("{}/{}/releases/latest", GITHUB_API, repo),
+    };
+    get_json(&url)
+}
+
+impl Release {
+    //校验文件与文件同名，后缀为 .sha256 或 .sha256.txt
+    //没有校验文件时返回 Ok(None)，有但获取失败时返回错误
+    pub fn checksum_of(&self, asset: &Asset) -> Result<Option<String>, String> {
+        self.assets
+            .iter()
+            .find(|x| x.name.starts_with(&asset.name) && x.name.contains(".sha256"))
+            .map(|x| get_checksum(&x.browser_download_url))
+            .transpose()
+    }
+}
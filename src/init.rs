@@ -0,0 +1,127 @@
+use std::{fs, path::Path};
+
+use log::{info, warn};
+use toml_edit::{value, DocumentMut};
+
+use crate::{
+    config::{self, check::parse_coordinate, TEMPLATE},
+    pom::{self, version},
+    utils::fail,
+};
+
+//预设附带的插件，获取不到最新版本时使用这里的版本
+const NATIVE_PLUGIN: (&str, &str) = ("org.itxtech:mirai-native", "2.0.0");
+const HTTP_API_PLUGIN: (&str, &str) = ("net.mamoe:mirai-api-http", "2.0.0");
+
+//maven 上最新的正式版本
+fn latest_release(project: &str) -> Option<String> {
+    let (group_id, artifact_id) = parse_coordinate(project)?;
+    pom::get_versions(group_id, artifact_id)?
+        .into_iter()
+        .rev()
+        .find(|x| version::is_release(x))
+}
+
+//mirua init [--preset minimal|native|http-api] [--mirai-version <版本>] [--plugin <坐标>[=<版本>]]... [--force]
+//accept_defaults 时配置文件已经存在就保留，容器再次启动时不会失败
+pub fn init(config_path: &str, args: &[String], accept_defaults: bool) {
+    let mut preset = "minimal";
+    let mut mirai_version = None;
+    let mut plugins = Vec::new();
+    let mut force = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut next = || {
+            args.next()
+                .unwrap_or_else(|| fail(format!("{} 后面需要参数", arg)))
+        };
+        match arg.as_str() {
+            "--preset" => preset = next(),
+            "--mirai-version" => mirai_version = Some(next()),
+            "--plugin" => plugins.push(next()),
+            "--force" => force = true,
+            x => fail(format!(
+                "未知参数 {}，可用参数：--preset --mirai-version --plugin --force",
+                x
+            )),
+        }
+    }
+
+    if Path::new(config_path).exists() && !force {
+        if accept_defaults {
+            info!("{} 已经存在，保留现有的配置", config_path);
+            return;
+        }
+        fail(format!("{} 已经存在，覆盖请加上 --force", config_path));
+    }
+
+    let mut plugins: Vec<(String, Option<String>)> = plugins
+        .into_iter()
+        .map(|x| match x.split_once('=') {
+            Some((project, version)) => (project.to_owned(), Some(version.to_owned())),
+            None => (x.to_owned(), None),
+        })
+        .collect();
+    let mut native = false;
+    match preset {
+        "minimal" => {}
+        "native" => {
+            native = true;
+            plugins.push((NATIVE_PLUGIN.0.to_owned(), None));
+        }
+        "http-api" => plugins.push((HTTP_API_PLUGIN.0.to_owned(), None)),
+        x => fail(format!("未知预设 {}，可选 minimal native http-api", x)),
+    }
+
+    let mut doc = TEMPLATE
+        .parse::<DocumentMut>()
+        .expect("解析配置文件模板失败");
+
+    if let Some(mirai_version) = mirai_version {
+        let full = config::table_mut(&mut doc, &["mirai", "full"]);
+        let projects: Vec<_> = full.iter().map(|(k, _)| k.to_owned()).collect();
+        for project in projects {
+            config::replace_value(&mut full[project.as_str()], mirai_version.as_str());
+        }
+    }
+
+    //mirai-native 只能在 32 位 jre 上运行
+    if native {
+        config::table_mut(&mut doc, &["jre"])["arch"] = value("x32");
+    }
+
+    for (project, version) in plugins {
+        if parse_coordinate(&project).is_none() {
+            fail(format!(
+                "{} 不是合法的坐标，格式为 <groupId>:<artifactId>",
+                project
+            ));
+        }
+        let version = match version {
+            Some(x) => x,
+            None => {
+                let fallback = [NATIVE_PLUGIN, HTTP_API_PLUGIN]
+                    .iter()
+                    .find(|(x, _)| *x == project)
+                    .map(|(_, x)| x.to_string());
+                match (latest_release(&project), fallback) {
+                    (Some(x), _) => x,
+                    (None, Some(x)) => {
+                        warn!("无法获取 {} 的最新版本，使用 {}", project, x);
+                        x
+                    }
+                    (None, None) => fail(format!(
+                        "无法获取 {} 的最新版本，请用 --plugin {}=<版本> 指定",
+                        project, project
+                    )),
+                }
+            }
+        };
+        info!("添加插件 {} {}", project, version);
+        config::table_mut(&mut doc, &["mirai", "plugins"])[project.as_str()] = value(version);
+    }
+
+    fs::write(config_path, doc.to_string()).expect("写入文件失败");
+    info!("已按预设 {} 生成 {}", preset, config_path);
+}
@@ -0,0 +1,329 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, warn};
+use zip::ZipArchive;
+
+use crate::{
+    config::{Config, Plugin},
+    manifest::Manifest,
+    MIRAI_PATH, MIRAI_PLUGINS_PATH,
+};
+
+//mirai-console 通过这个服务声明找到插件的主类
+const PLUGIN_SERVICE: &str = "META-INF/services/net.mamoe.mirai.console.plugin.jvm.JvmPlugin";
+const PLUGIN_DESCRIPTION: &str = "plugin.yml";
+
+//plugin.yml 中关心的几项
+#[derive(Debug, Default)]
+pub struct Description {
+    pub id: Option<String>,
+    pub version: Option<String>,
+    //"<id>[:<版本要求>][?]"，? 表示可选
+    pub dependencies: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Jar {
+    entries: Vec<String>,
+    pub description: Option<Description>,
+}
+
+impl Jar {
+    //按 zip 打开，文件不是 zip 时返回错误
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Jar, String> {
+        let file = File::open(&path).map_err(|e| e.to_string())?;
+        let mut archive = ZipArchive::new(file).map_err(|e| e.to_string())?;
+        let entries = archive.file_names().map(|x| x.to_owned()).collect();
+        let description = match archive.by_name(PLUGIN_DESCRIPTION) {
+            Ok(mut file) => {
+                let mut buf = String::new();
+                file.read_to_string(&mut buf).map_err(|e| e.to_string())?;
+                Some(parse_description(&buf))
+            }
+            Err(_) => None,
+        };
+        Ok(Jar {
+            entries,
+            description,
+        })
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.entries.iter().any(|x| x == name)
+    }
+
+    fn has_classes(&self, prefix: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|x| x.starts_with(prefix) && x.ends_with(".class"))
+    }
+
+    fn has_sources(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|x| x.ends_with(".java") || x.ends_with(".kt"))
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|x| x.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|x| x.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+//plugin.yml 只有几项简单的键值与字符串列表，按行读取即可
+fn parse_description(buf: &str) -> Description {
+    let mut description = Description::default();
+    let mut in_dependencies = false;
+    for line in buf.lines() {
+        let line = line.split(" #").next().unwrap();
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) || line.starts_with('-') {
+            if let (true, Some(item)) = (in_dependencies, line.trim().strip_prefix('-')) {
+                description.dependencies.push(unquote(item).to_owned());
+            }
+            continue;
+        }
+        in_dependencies = false;
+        let (key, value) = match line.split_once(':') {
+            Some(x) => x,
+            None => continue,
+        };
+        let value = unquote(value);
+        match key.trim() {
+            "id" => description.id = Some(value.to_owned()),
+            "version" => description.version = Some(value.to_owned()),
+            "dependencies" => match value.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+                Some(list) => description.dependencies.extend(
+                    list.split(',')
+                        .map(unquote)
+                        .filter(|x| !x.is_empty())
+                        .map(|x| x.to_owned()),
+                ),
+                None => in_dependencies = true,
+            },
+            _ => {}
+        }
+    }
+    description
+}
+
+//mirai-console 无法加载的插件 jar，返回原因
+fn plugin_problem(jar: &Jar) -> Option<String> {
+    if jar.contains(PLUGIN_SERVICE) {
+        None
+    } else if jar.has_sources() && !jar.has_classes("") {
+        Some("只包含源码，不能运行".to_owned())
+    } else {
+        Some(format!(
+            "中没有 {}，mirai-console 不会加载它",
+            PLUGIN_SERVICE
+        ))
+    }
+}
+
+//没有安装的必需依赖，返回依赖的 id，可选的依赖不检查
+fn missing_dependency<'a>(dependency: &'a str, ids: &HashMap<String, String>) -> Option<&'a str> {
+    if dependency.ends_with('?') {
+        return None;
+    }
+    let id = dependency.split(':').next().unwrap().trim();
+    Some(id).filter(|x| !ids.contains_key(*x))
+}
+
+fn files(manifest: &Manifest, dir: &str, coordinate: &str) -> Vec<PathBuf> {
+    manifest
+        .files_of(coordinate)
+        .into_iter()
+        .map(|x| Path::new(dir).join(x))
+        .filter(|x| x.exists())
+        .collect()
+}
+
+//启动前检查下载的 jar，发现问题只提示，不阻止启动
+pub fn check(config: &Config, content_manifest: &Manifest, plugins_manifest: &Manifest) {
+    for (project, version) in config.mirai.full.iter() {
+        let coordinate = format!("{}:{}", project, version);
+        for path in files(content_manifest, MIRAI_PATH, &coordinate) {
+            match Jar::open(&path) {
+                Ok(jar) if jar.has_classes("net/mamoe/mirai/") => {}
+                Ok(_) => warn!(
+                    "{} 中没有 mirai 的类，不是 {} 的完整打包",
+                    path.display(),
+                    project
+                ),
+                Err(e) => warn!(
+                    "{} 不是有效的 jar，可能下载到了错误页面，删除后重新启动会重新下载，{}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+    }
+
+    //plugin.yml 中的 id -> 配置中的插件名
+    let mut ids = HashMap::new();
+    let mut dependencies = Vec::new();
+    let mut names: Vec<_> = config.mirai.plugins.iter().collect();
+    names.sort_by_key(|(name, _)| *name);
+    for (name, plugin) in names {
+        for path in files(plugins_manifest, MIRAI_PLUGINS_PATH, &plugin.identity(name)) {
+            let jar = match Jar::open(&path) {
+                Ok(x) => x,
+                Err(e) => {
+                    warn!(
+                        "插件 {} 的 {} 不是有效的 jar，可能下载到了错误页面，删除后重新启动会重新下载，{}",
+                        name,
+                        path.display(),
+                        e
+                    );
+                    continue;
+                }
+            };
+            if let Some(problem) = plugin_problem(&jar) {
+                warn!("插件 {} 的 {} {}", name, path.display(), problem);
+            }
+            let description = match jar.description {
+                Some(x) => x,
+                None => continue,
+            };
+            debug!("{} 的 plugin.yml：{:?}", path.display(), description);
+            if let (Plugin::Maven(version), Some(declared)) = (plugin, &description.version) {
+                if version != declared {
+                    warn!(
+                        "插件 {} 配置的版本为 {}，plugin.yml 中声明的是 {}",
+                        name, version, declared
+                    );
+                }
+            }
+            if let Some(id) = description.id {
+                if let Some(other) = ids.insert(id.to_owned(), name.to_owned()) {
+                    warn!("插件 {} 与 {} 的 id 都是 {}", other, name, id);
+                }
+            }
+            for dependency in description.dependencies {
+                dependencies.push((name.to_owned(), dependency));
+            }
+        }
+    }
+
+    for (name, dependency) in dependencies {
+        if let Some(id) = missing_dependency(&dependency, &ids) {
+            warn!(
+                "插件 {} 依赖 {}，但没有找到 id 为 {} 的插件",
+                name, dependency, id
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{fs, io::Write};
+    use zip::{write::FileOptions, ZipWriter};
+
+    fn write_jar(path: &Path, entries: &[(&str, &str)]) {
+        let mut writer = ZipWriter::new(File::create(path).unwrap());
+        for (name, content) in entries {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn block_list() {
+        let description = parse_description(
+            "id: \"org.example.chat\" # 插件 id\nversion: '1.2.0'\ndependencies:\n  - org.example.core:[1.0,2.0)\n  - \"org.example.extra?\" # 可选\n- org.example.top\nname: Chat\n  - not.a.dependency\n",
+        );
+        assert_eq!(description.id.as_deref(), Some("org.example.chat"));
+        assert_eq!(description.version.as_deref(), Some("1.2.0"));
+        assert_eq!(
+            description.dependencies,
+            [
+                "org.example.core:[1.0,2.0)",
+                "org.example.extra?",
+                "org.example.top"
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_list() {
+        let description = parse_description(
+            "# plugin.yml\nid: org.example.chat\ndependencies: [ \"org.example.core\", 'org.example.extra?' ] # 依赖\n",
+        );
+        assert_eq!(
+            description.dependencies,
+            ["org.example.core", "org.example.extra?"]
+        );
+        assert!(parse_description("dependencies: []\n")
+            .dependencies
+            .is_empty());
+        assert_eq!(parse_description("version: 1.0\n").id, None);
+    }
+
+    #[test]
+    fn optional_dependencies() {
+        let description = parse_description(
+            "dependencies:\n  - org.example.core:[1.0,2.0)\n  - org.example.extra:1.0?\n  - org.example.other?\n",
+        );
+        let ids: HashMap<_, _> = vec![("org.example.chat".to_owned(), "chat".to_owned())]
+            .into_iter()
+            .collect();
+        let missing: Vec<_> = description
+            .dependencies
+            .iter()
+            .filter_map(|x| missing_dependency(x, &ids))
+            .collect();
+        assert_eq!(missing, ["org.example.core"]);
+        assert_eq!(missing_dependency("org.example.chat:1.0", &ids), None);
+    }
+
+    #[test]
+    fn plugin_jars() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = dir.path().join("plugin.jar");
+        write_jar(
+            &path,
+            &[
+                (PLUGIN_SERVICE, "org.example.Chat\n"),
+                ("org/example/Chat.class", ""),
+                (PLUGIN_DESCRIPTION, "id: org.example.chat\nversion: 1.2.0\n"),
+            ],
+        );
+        let jar = Jar::open(&path).unwrap();
+        assert_eq!(plugin_problem(&jar), None);
+        assert_eq!(jar.description.unwrap().version.as_deref(), Some("1.2.0"));
+
+        let path = dir.path().join("sources.jar");
+        write_jar(&path, &[("org/example/Chat.kt", "class Chat")]);
+        let jar = Jar::open(&path).unwrap();
+        assert!(jar.description.is_none());
+        assert!(plugin_problem(&jar).unwrap().contains("源码"));
+
+        let path = dir.path().join("classes.jar");
+        write_jar(&path, &[("org/example/Chat.class", "")]);
+        assert!(plugin_problem(&Jar::open(&path).unwrap())
+            .unwrap()
+            .contains(PLUGIN_SERVICE));
+
+        //下载到的错误页面
+        let path = dir.path().join("error.jar");
+        fs::write(&path, "<html>404 Not Found</html>").unwrap();
+        assert!(Jar::open(&path).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    collections::HashSet,
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use log::debug;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct JavaVersion {
+    pub feature: u32,
+    pub interim: u32,
+    pub update: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct JavaInstallation {
+    pub path: PathBuf,
+    pub version: JavaVersion,
+    pub vendor: String,
+    pub bits: u32,
+}
+
+fn java_exe() -> &'static str {
+    if cfg!(windows) {
+        "java.exe"
+    } else {
+        "java"
+    }
+}
+
+//"1.8.0_292" -> 8.0.292，"17.0.9" -> 17.0.9，"21-ea" -> 21.0.0
+fn parse_version(version: &str) -> Option<JavaVersion> {
+    let mut parts = version
+        .split(|c: char| !c.is_ascii_digit())
+        .take_while(|x| !x.is_empty())
+        .map(|x| x.parse::<u32>().ok());
+    let mut next = || parts.next().flatten();
+
+    let feature = match next()? {
+        1 => next()?,
+        x => x,
+    };
+    Some(JavaVersion {
+        feature,
+        interim: next().unwrap_or(0),
+        update: next().unwrap_or(0),
+    })
+}
+
+fn parse_vendor(output: &str) -> &'static str {
+    //按出现频率排，OpenJ9 放最后，AdoptOpenJDK 也出过 OpenJ9
+    const VENDORS: [(&str, &str); 11] = [
+        ("Temurin", "temurin"),
+        ("Semeru", "semeru"),
+        ("Zulu", "zulu"),
+        ("Dragonwell", "dragonwell"),
+        ("Corretto", "corretto"),
+        ("Microsoft", "microsoft"),
+        ("GraalVM", "graalvm"),
+        ("AdoptOpenJDK", "adoptopenjdk"),
+        ("Java(TM)", "oracle"),
+        ("OpenJ9", "openj9"),
+        ("OpenJDK", "openjdk"),
+    ];
+    VENDORS
+        .iter()
+        .find(|(keyword, _)| output.contains(keyword))
+        .map(|(_, vendor)| *vendor)
+        .unwrap_or("unknown")
+}
+
+//解析 java -version 的输出（在 stderr 里）
+pub fn parse_version_output(output: &str) -> Option<(JavaVersion, String, u32)> {
+    let first_line = output.lines().find(|x| x.contains("version \""))?;
+    let version = first_line.split('"').nth(1)?;
+    let version = parse_version(version)?;
+    let bits = if output.contains("64-Bit") || output.contains("64-bit") {
+        64
+    } else {
+        32
+    };
+    Some((version, parse_vendor(output).to_owned(), bits))
+}
+
+pub fn inspect<P: AsRef<Path>>(java_path: P) -> Option<JavaInstallation> {
+    let java_path = java_path.as_ref();
+    let output = Command::new(java_path).arg("-version").output().ok()?;
+    let output = String::from_utf8_lossy(&output.stderr);
+    let (version, vendor, bits) = parse_version_output(&output)?;
+    Some(JavaInstallation {
+        path: java_path.to_owned(),
+        version,
+        vendor,
+        bits,
+    })
+}
+
+fn glob_java(pattern: &Path) -> Vec<PathBuf> {
+    match glob::glob(&pattern.to_string_lossy()) {
+        Ok(paths) => paths.filter_map(Result::ok).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+//JAVA_HOME -> PATH -> /usr/lib/jvm 或 /Library/Java -> SDKMAN
+fn candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    if let Some(java_home) = env::var_os("JAVA_HOME") {
+        candidates.push(Path::new(&java_home).join("bin").join(java_exe()));
+    }
+    if let Some(path) = env::var_os("PATH") {
+        candidates.extend(env::split_paths(&path).map(|x| x.join(java_exe())));
+    }
+    if cfg!(unix) {
+        candidates.extend(glob_java(Path::new("/usr/lib/jvm/*/bin/java")));
+        //java 8 的 jdk 把 jre 放在子目录
+        candidates.extend(glob_java(Path::new("/usr/lib/jvm/*/jre/bin/java")));
+    }
+    if cfg!(target_os = "macos") {
+        candidates.extend(glob_java(Path::new(
+            "/Library/Java/JavaVirtualMachines/*/Contents/Home/bin/java",
+        )));
+    }
+
+    let sdkman = env::var_os("SDKMAN_DIR")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|x| Path::new(&x).join(".sdkman")));
+    if let Some(sdkman) = sdkman {
+        candidates.extend(glob_java(
+            &sdkman
+                .join("candidates")
+                .join("java")
+                .join("*")
+                .join("bin")
+                .join(java_exe()),
+        ));
+    }
+
+    //软链接指向同一个 java 的只留一个
+    let mut seen = HashSet::new();
+    candidates
+        .into_iter()
+        .filter(|x| x.is_file())
+        .filter(|x| seen.insert(fs::canonicalize(x).unwrap_or_else(|_| x.to_owned())))
+        .collect()
+}
+
+//找满足最低版本与位数的 java，优先大版本完全一致的，其次发行版一致的，再其次版本高的
+pub fn find(min_version: u32, bits: u32, vendor: Option<&str>) -> Option<JavaInstallation> {
+    candidates()
+        .into_iter()
+        .filter_map(inspect)
+        .inspect(|x| debug!("发现 java {:?}", x))
+        .filter(|x| x.version.feature >= min_version && x.bits == bits)
+        .max_by_key(|x| {
+            (
+                x.version.feature == min_version,
+                Some(x.vendor.as_str()) == vendor,
+                x.version,
+            )
+        })
+}
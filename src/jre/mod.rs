@@ -0,0 +1,185 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use log::{debug, info, warn};
+
+pub mod discover;
+pub mod store;
+mod vendor;
+use store::JreKey;
+use vendor::Platform;
+
+use crate::{config::JRE, utils};
+
+//未指定版本时使用的 java 大版本
+const DEFAULT_JRE_VERSION: u32 = 11;
+
+pub fn check_jre(jre_path: &str) -> bool {
+    debug!("jre_path: {}", jre_path);
+    let jre_path = Path::new(jre_path);
+    if !jre_path.exists() && !jre_path.with_extension("exe").exists() {
+        info!("找不到 jar_path 指定的 java");
+        return false;
+    }
+    match Command::new(jre_path).arg("-version").output() {
+        Ok(output) => info!("jre 版本：\n{}", String::from_utf8_lossy(&output.stderr)),
+        Err(e) => panic!("jre_path 指定的 java 损坏，请考虑重新下载。错误：{}", e),
+    }
+    true
+}
+
+//mac 的 jre 是 Contents/Home/bin/java 的结构
+pub fn java_bin<P: AsRef<Path>>(jre_root: P) -> PathBuf {
+    let jre_root = jre_root.as_ref();
+    let mac_home = jre_root.join("Contents").join("Home");
+    let jre_root = if mac_home.join("bin").is_dir() {
+        mac_home
+    } else {
+        jre_root.to_owned()
+    };
+    jre_root.join("bin").join("java")
+}
+
+//Alpine 等使用 musl 的系统无法运行 glibc 编译的 jre
+fn is_musl() -> bool {
+    cfg!(target_env = "musl")
+        || Path::new("/etc/alpine-release").exists()
+        || glob::glob("/lib/ld-musl-*")
+            .map(|mut x| x.next().is_some())
+            .unwrap_or(false)
+}
+
+fn os() -> (&'static str, &'static str) {
+    if cfg!(target_os = "windows") {
+        ("windows", "zip")
+    } else if cfg!(target_os = "linux") {
+        if is_musl() {
+            ("alpine-linux", "tar.gz")
+        } else {
+            ("linux", "tar.gz")
+        }
+    } else if cfg!(target_os = "macos") {
+        ("mac", "tar.gz")
+    } else {
+        panic!("这啥系统啊");
+    }
+}
+
+fn native_arch() -> &'static str {
+    if cfg!(target_arch = "x86") {
+        "x32"
+    } else if cfg!(target_arch = "x86_64") {
+        "x64"
+    } else if cfg!(target_arch = "arm") {
+        "arm"
+    } else if cfg!(target_arch = "aarch64") {
+        "aarch64"
+    } else {
+        panic!("这啥架构啊")
+    }
+}
+
+//在系统中找一个满足配置要求的 java，找不到再下载
+pub fn find_system_jre(config: &JRE) -> Option<PathBuf> {
+    if config.discover == Some(false) {
+        return None;
+    }
+
+    let key = JreKey::from_config(config);
+    let bits = match key.arch.as_str() {
+        "x32" | "arm" => 32,
+        _ => 64,
+    };
+    info!("查找系统中 {} 位、版本不低于 {} 的 java", bits, key.version);
+
+    let found = discover::find(key.version, bits, Some(key.vendor.name()))?;
+    info!(
+        "使用系统中的 java {}.{}.{} ({}) {}",
+        found.version.feature,
+        found.version.interim,
+        found.version.update,
+        found.vendor,
+        found.path.display()
+    );
+    Some(found.path)
+}
+
+//下载 jre 到 <store>/<key>
+pub fn get_jre(store: &Path, key: &JreKey, config: &JRE) {
+    let jre_path = store.join(key.to_string());
+    let vendor = key.vendor;
+
+    let (os, ext) = os();
+    debug!("OS: {}", os);
+
+    let platform = Platform {
+        os,
+        arch: &key.arch,
+        ext,
+    };
+    let release = match config.url_template.as_deref() {
+        Some(template) => vendor::from_template(
+            config.mirror.as_deref(),
+            template,
+            vendor,
+            key.version,
+            &platform,
+        ),
+        None => vendor::latest(vendor, key.version, &platform),
+    }
+    .unwrap_or_else(|e| panic!("查找可用的 jre 失败，{}", e));
+
+    info!(
+        "开始从 {} 下载 {} {}",
+        release.url,
+        vendor.name(),
+        release.version
+    );
+
+    //下载与解压都在同一文件系统的临时目录中进行，失败时 TempDir 析构会自动清理
+    let temp_dir = tempfile::Builder::new()
+        .prefix(".mirua-jre-")
+        .tempdir_in(store)
+        .expect("创建临时目录失败");
+    let archive_path = temp_dir.path().join(&release.name);
+    let sha256 = utils::download_file(&release.url, &archive_path);
+
+    match release.sha256.as_deref() {
+        Some(expected) if !expected.eq_ignore_ascii_case(&sha256) => {
+            panic!("jre 校验失败，期望 sha256 {}，实际 {}", expected, sha256)
+        }
+        Some(_) => info!("sha256 校验通过"),
+        None => warn!("自定义镜像没有提供 sha256，跳过校验"),
+    }
+
+    info!("提取 jre...");
+    let extract_path = temp_dir.path().join("extract");
+    utils::extract(&archive_path, &extract_path).unwrap_or_else(|e| panic!("{}", e));
+    let jre_root = find_jre_root(&extract_path).expect("我那么大一个jre目录呢？");
+    debug!("{} 解压到 {}", release.name, jre_root.display());
+
+    //上次留下的残缺 jre 先挪进临时目录，随临时目录一起删除
+    if jre_path.exists() {
+        fs::rename(&jre_path, temp_dir.path().join("old")).expect("移除旧的 jre 失败");
+    }
+    fs::rename(jre_root, &jre_path).expect("移动 jre 失败");
+    info!("jre 已安装到 {}", jre_path.display());
+}
+
+//压缩包顶层通常只有一个目录，各家命名不同，按结构判断
+fn find_jre_root(extract_path: &Path) -> Option<PathBuf> {
+    let entries: Vec<_> = fs::read_dir(extract_path)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|x| x.path())
+        .collect();
+
+    match entries.as_slice() {
+        [dir] if dir.is_dir() => Some(dir.to_owned()),
+        _ if extract_path.join("bin").is_dir() => Some(extract_path.to_owned()),
+        _ => None,
+    }
+}
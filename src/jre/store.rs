@@ -0,0 +1,240 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, info, warn};
+
+use super::{discover, get_jre, java_bin, native_arch, os, vendor::Vendor, DEFAULT_JRE_VERSION};
+use crate::{
+    config::{self, Config, JRE},
+    utils::fail,
+    CONFIG_PATH, JRE_PATH,
+};
+
+//每个 jre 存放在 <store>/<vendor>-<version>-<arch> 目录下
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JreKey {
+    pub vendor: Vendor,
+    pub version: u32,
+    pub arch: String,
+}
+
+//拆分 <发行版>-<版本>-<架构>
+fn split_key(key: &str) -> Option<(Vendor, u32, &str)> {
+    let mut parts = key.splitn(3, '-');
+    let vendor = Vendor::from_name(parts.next()?)?;
+    let version = parts.next()?.parse().ok()?;
+    let arch = parts.next()?;
+    if !["x32", "x64", "arm", "aarch64"].contains(&arch) {
+        return None;
+    }
+    Some((vendor, version, arch))
+}
+
+impl JreKey {
+    pub fn from_config(config: &JRE) -> JreKey {
+        let arch = match config.arch.as_deref() {
+            Some(arch) => arch,
+            None => native_arch(),
+        };
+        let (os, _) = os();
+
+        let mut vendor =
+            Vendor::from_config(config.vendor.as_deref(), config.implementation.as_deref());
+        if !vendor.supports(os, arch) {
+            debug!("OpenJ9 不提供 {} {} 的 jre，改用 Temurin", os, arch);
+            vendor = Vendor::Temurin;
+        }
+
+        JreKey {
+            vendor,
+            version: config.version.unwrap_or(DEFAULT_JRE_VERSION),
+            arch: arch.to_owned(),
+        }
+    }
+
+    //temurin-17-x64，发行版不提供该平台的 jre 时报错，不像 from_config 那样换成 Temurin
+    pub fn parse(key: &str) -> Result<JreKey, String> {
+        let (vendor, version, arch) = split_key(key).ok_or_else(|| {
+            format!(
+                "无法识别 {}，格式为 <发行版>-<版本>-<架构>，发行版可选 temurin semeru zulu dragonwell，架构可选 x32 x64 arm aarch64",
+                key
+            )
+        })?;
+
+        let (os, _) = os();
+        if !vendor.supports(os, arch) {
+            return Err(format!(
+                "{} 不提供 {} {} 的 jre，可以改用 temurin-{}-{}",
+                vendor.name(),
+                os,
+                arch,
+                version,
+                arch
+            ));
+        }
+        Ok(JreKey {
+            vendor,
+            version,
+            arch: arch.to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for JreKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}-{}", self.vendor.name(), self.version, self.arch)
+    }
+}
+
+pub fn store_path(config: &JRE) -> PathBuf {
+    PathBuf::from(config.store.as_deref().unwrap_or(JRE_PATH))
+}
+
+//旧版本只有一个 ./runtime，按 java -version 的结果挪进对应的子目录
+fn migrate_legacy(store: &Path) {
+    if !store.join("bin").is_dir() && !store.join("Contents").is_dir() {
+        return;
+    }
+    let installation = match discover::inspect(java_bin(store)) {
+        Some(x) => x,
+        None => {
+            warn!("无法识别 {} 中的 jre，跳过迁移", store.display());
+            return;
+        }
+    };
+
+    //旧版本 64 位下载的是 OpenJ9，32 位下载的是 HotSpot
+    let vendor = match installation.vendor.as_str() {
+        x @ ("temurin" | "semeru" | "zulu" | "dragonwell") => Vendor::from_config(Some(x), None),
+        _ if installation.bits == 32 => Vendor::Temurin,
+        _ => Vendor::Semeru,
+    };
+    let key = JreKey {
+        vendor,
+        version: installation.version.feature,
+        arch: if installation.bits == 32 {
+            "x32".to_owned()
+        } else {
+            native_arch().to_owned()
+        },
+    };
+
+    let temp_path = store.with_file_name(".mirua-runtime-legacy");
+    fs::rename(store, &temp_path).expect("迁移旧的 jre 失败");
+    fs::create_dir_all(store).expect("迁移旧的 jre 失败");
+    fs::rename(&temp_path, store.join(key.to_string())).expect("迁移旧的 jre 失败");
+    info!(
+        "旧的 jre 已迁移到 {}",
+        store.join(key.to_string()).display()
+    );
+}
+
+fn open_store(config: &JRE) -> PathBuf {
+    let store = store_path(config);
+    fs::create_dir_all(&store).expect("自动创建目录失败");
+    migrate_legacy(&store);
+    store
+}
+
+//按配置选中的 jre 中 java 的路径
+pub fn managed_java(config: &JRE) -> PathBuf {
+    let store = open_store(config);
+    java_bin(store.join(JreKey::from_config(config).to_string()))
+}
+
+pub fn install(config: &JRE) {
+    let store = open_store(config);
+    get_jre(&store, &JreKey::from_config(config), config);
+}
+
+fn installed(store: &Path) -> Vec<String> {
+    let mut installed: Vec<_> = fs::read_dir(store)
+        .map(|x| {
+            x.filter_map(Result::ok)
+                .filter(|x| x.path().is_dir())
+                .filter_map(|x| x.file_name().into_string().ok())
+                .filter(|x| !x.starts_with('.'))
+                .collect()
+        })
+        .unwrap_or_default();
+    installed.sort();
+    installed
+}
+
+fn parse_key_arg(arg: Option<&String>) -> JreKey {
+    let arg = arg.unwrap_or_else(|| fail("需要指定 jre，例如 temurin-17-x64".to_owned()));
+    JreKey::parse(arg).unwrap_or_else(|e| fail(e))
+}
+
+//mirua jre list|install|remove|use
+pub fn command(config: &Config, args: &[String]) {
+    let store = open_store(&config.jre);
+    let current = JreKey::from_config(&config.jre).to_string();
+
+    match args.first().map(String::as_str) {
+        None | Some("list") => {
+            for name in installed(&store) {
+                let mark = if name == current { "*" } else { " " };
+                let detail = discover::inspect(java_bin(store.join(&name)))
+                    .map(|x| {
+                        format!(
+                            "{}.{}.{} {} {}位",
+                            x.version.feature,
+                            x.version.interim,
+                            x.version.update,
+                            x.vendor,
+                            x.bits
+                        )
+                    })
+                    .unwrap_or_else(|| "已损坏".to_owned());
+                println!("{} {}  {}", mark, name, detail);
+            }
+            if !store.join(&current).is_dir() {
+                println!("  当前配置使用的 {} 尚未安装", current);
+            }
+        }
+        Some("install") => {
+            let key = match args.get(1) {
+                Some(arg) => parse_key_arg(Some(arg)),
+                None => JreKey::from_config(&config.jre),
+            };
+            if store.join(key.to_string()).is_dir() {
+                info!("{} 已经安装", key);
+                return;
+            }
+            get_jre(&store, &key, &config.jre);
+        }
+        Some("remove") => {
+            let key = parse_key_arg(args.get(1)).to_string();
+            let path = store.join(&key);
+            if !path.is_dir() {
+                warn!("{} 没有安装", key);
+                return;
+            }
+            fs::remove_dir_all(&path).expect("删除 jre 失败");
+            info!("已删除 {}", key);
+            if key == current {
+                warn!("删除的是当前配置使用的 jre，下次启动时会重新下载");
+            }
+        }
+        Some("use") => {
+            let key = parse_key_arg(args.get(1));
+            config::edit(CONFIG_PATH, |doc| {
+                let jre = config::table_mut(doc, &["jre"]);
+                config::replace_value(&mut jre["vendor"], key.vendor.name());
+                config::replace_value(&mut jre["version"], key.version as i64);
+                config::replace_value(&mut jre["arch"], key.arch.as_str());
+            });
+            info!("已切换到 {}", key);
+            if !store.join(key.to_string()).is_dir() {
+                info!("{} 尚未安装，下次启动时会自动下载", key);
+            }
+        }
+        Some(x) => {
+            log::error!("未知命令 jre {}，可用命令：list install remove use", x);
+            std::process::exit(1);
+        }
+    }
+}
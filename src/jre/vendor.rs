@@ -0,0 +1,279 @@
+use log::{debug, warn};
+use serde::Deserialize;
+
+use crate::{
+    github,
+    utils::{get_checksum, get_json},
+};
+
+const ADOPTIUM_API: &str = "https://api.adoptium.net/v3/assets/latest";
+const ZULU_API: &str = "https://api.azul.com/metadata/v1/zulu/packages";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vendor {
+    Temurin,
+    Semeru,
+    Zulu,
+    Dragonwell,
+}
+
+impl Vendor {
+    //发行版名称，不区分大小写，无法识别时返回 None
+    pub fn from_name(name: &str) -> Option<Vendor> {
+        match name.to_lowercase().as_str() {
+            "temurin" | "adoptium" => Some(Vendor::Temurin),
+            "semeru" | "openj9" => Some(Vendor::Semeru),
+            "zulu" => Some(Vendor::Zulu),
+            "dragonwell" => Some(Vendor::Dragonwell),
+            _ => None,
+        }
+    }
+
+    //vendor 优先，没写 vendor 时按 impl 选择，都没写就是 OpenJ9
+    pub fn from_config(vendor: Option<&str>, implementation: Option<&str>) -> Vendor {
+        let implementation = implementation.map(|x| x.to_lowercase());
+        match (vendor, implementation.as_deref()) {
+            (Some(x), _) => {
+                Vendor::from_name(x).unwrap_or_else(|| panic!("不支持的 jre 发行版 {}", x))
+            }
+            (None, Some("hotspot")) => Vendor::Temurin,
+            (None, Some("openj9")) | (None, None) => Vendor::Semeru,
+            (None, Some(x)) => panic!("不支持的 jvm 实现 {}", x),
+        }
+    }
+
+    //OpenJ9 不提供 32 位、arm 与 musl 的 jre
+    pub fn supports(&self, os: &str, arch: &str) -> bool {
+        !(*self == Vendor::Semeru && (arch == "x32" || arch == "arm" || os == "alpine-linux"))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Vendor::Temurin => "temurin",
+            Vendor::Semeru => "semeru",
+            Vendor::Zulu => "zulu",
+            Vendor::Dragonwell => "dragonwell",
+        }
+    }
+}
+
+//arch 与 mirua.toml 中的写法一致：x32 x64 arm aarch64
+//os 沿用 Adoptium 的写法：windows linux alpine-linux mac
+//ext 不带点：tar.gz zip
+#[derive(Debug)]
+pub struct Platform<'a> {
+    pub os: &'a str,
+    pub arch: &'a str,
+    pub ext: &'a str,
+}
+
+#[derive(Debug)]
+pub struct Release {
+    pub name: String,
+    pub url: String,
+    pub version: String,
+    pub sha256: Option<String>,
+}
+
+//自定义镜像，url-template 中可以使用 {version} {arch} {os} {impl} {ext}
+//相对路径会拼接在 mirror 之后，校验值从同名的 .sha256.txt 获取
+//只有自定义镜像允许没有校验值
+pub fn from_template(
+    mirror: Option<&str>,
+    template: &str,
+    vendor: Vendor,
+    version: u32,
+    platform: &Platform,
+) -> Result<Release, String> {
+    let implementation = match vendor {
+        Vendor::Semeru => "openj9",
+        _ => "hotspot",
+    };
+    let path = template
+        .replace("{version}", &version.to_string())
+        .replace("{arch}", platform.arch)
+        .replace("{os}", platform.os)
+        .replace("{impl}", implementation)
+        .replace("{ext}", platform.ext);
+
+    let url = if path.contains("://") {
+        path
+    } else {
+        let mirror = mirror.ok_or("url-template 是相对路径时需要设置 mirror")?;
+        format!(
+            "{}/{}",
+            mirror.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
+    };
+    let name = url
+        .rsplit('/')
+        .next()
+        .filter(|x| !x.is_empty())
+        .ok_or_else(|| format!("{} 中没有文件名", url))?
+        .to_owned();
+
+    let sha256 = match get_checksum(&format!("{}.sha256.txt", url)) {
+        Ok(x) => Some(x),
+        Err(e) => {
+            warn!("无法获取镜像提供的校验值，下载的 jre 将不会校验，{}", e);
+            None
+        }
+    };
+
+    Ok(Release {
+        sha256,
+        version: version.to_string(),
+        name,
+        url,
+    })
+}
+
+pub fn latest(vendor: Vendor, version: u32, platform: &Platform) -> Result<Release, String> {
+    debug!("查找 {} {} {:?}", vendor.name(), version, platform);
+    match vendor {
+        Vendor::Temurin => temurin(version, platform),
+        Vendor::Semeru => semeru(version, platform),
+        Vendor::Zulu => zulu(version, platform),
+        Vendor::Dragonwell => dragonwell(version, platform),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumAsset {
+    binary: AdoptiumBinary,
+    version: AdoptiumVersion,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumBinary {
+    package: AdoptiumPackage,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumPackage {
+    name: String,
+    link: String,
+    checksum: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumVersion {
+    openjdk_version: String,
+}
+
+fn temurin(version: u32, platform: &Platform) -> Result<Release, String> {
+    let url = format!(
+        "{}/{}/hotspot?architecture={}&image_type=jre&os={}&vendor=eclipse",
+        ADOPTIUM_API, version, platform.arch, platform.os
+    );
+    let assets: Vec<AdoptiumAsset> = get_json(&url)?;
+    let asset = assets
+        .into_iter()
+        .find(|x| x.binary.package.name.ends_with(platform.ext))
+        .ok_or_else(|| format!("Temurin 没有 {:?} 可用的 jre {}", platform, version))?;
+    let package = asset.binary.package;
+    let sha256 = match package.checksum {
+        Some(x) => x,
+        None => return Err(format!("Temurin 没有提供 {} 的 sha256", package.name)),
+    };
+
+    Ok(Release {
+        name: package.name,
+        url: package.link,
+        version: asset.version.openjdk_version,
+        sha256: Some(sha256),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct ZuluPackage {
+    package_uuid: String,
+    name: String,
+    download_url: String,
+    java_version: Vec<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZuluPackageDetail {
+    sha256_hash: Option<String>,
+}
+
+fn zulu(version: u32, platform: &Platform) -> Result<Release, String> {
+    let arch = match platform.arch {
+        "x32" => "i686",
+        x => x,
+    };
+    let os = match platform.os {
+        "mac" => "macos",
+        "alpine-linux" => "linux-musl",
+        x => x,
+    };
+    let url = format!(
+        "{}/?java_version={}&os={}&arch={}&archive_type={}&java_package_type=jre&latest=true&release_status=ga&availability_types=CA&page_size=1",
+        ZULU_API, version, os, arch, platform.ext
+    );
+    let packages: Vec<ZuluPackage> = get_json(&url)?;
+    let package = packages
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Zulu 没有 {:?} 可用的 jre {}", platform, version))?;
+    //列表里没有校验值，要单独查询
+    let detail: ZuluPackageDetail = get_json(&format!("{}/{}", ZULU_API, package.package_uuid))?;
+    let sha256 = detail
+        .sha256_hash
+        .ok_or_else(|| format!("Zulu 没有提供 {} 的 sha256", package.name))?;
+
+    Ok(Release {
+        version: package
+            .java_version
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join("."),
+        name: package.name,
+        url: package.download_url,
+        sha256: Some(sha256),
+    })
+}
+
+//在 GitHub 最新的 release 中找符合条件的文件
+fn github_latest<F: Fn(&str) -> bool>(repo: &str, matches: F) -> Result<Release, String> {
+    let release = github::release(repo, None)?;
+    let asset = release
+        .assets
+        .iter()
+        .find(|x| matches(&x.name))
+        .ok_or_else(|| format!("{} {} 中没有符合条件的文件", repo, release.tag_name))?;
+
+    let sha256 = release
+        .checksum_of(asset)?
+        .ok_or_else(|| format!("{} 没有提供 {} 的 sha256", repo, asset.name))?;
+
+    Ok(Release {
+        name: asset.name.to_owned(),
+        url: asset.browser_download_url.to_owned(),
+        version: release.tag_name.to_owned(),
+        sha256: Some(sha256),
+    })
+}
+
+fn semeru(version: u32, platform: &Platform) -> Result<Release, String> {
+    let arch = match platform.arch {
+        "x32" => "x86-32",
+        x => x,
+    };
+    let prefix = format!("ibm-semeru-open-jre_{}_{}_", arch, platform.os);
+    github_latest(&format!("ibmruntimes/semeru{}-binaries", version), |name| {
+        name.starts_with(&prefix) && name.ends_with(platform.ext)
+    })
+}
+
+fn dragonwell(version: u32, platform: &Platform) -> Result<Release, String> {
+    //dragonwell 只提供完整 jdk
+    let pattern = format!("_{}_{}.{}", platform.arch, platform.os, platform.ext);
+    github_latest(
+        &format!("dragonwell-project/dragonwell{}", version),
+        |name| name.starts_with("Alibaba_Dragonwell") && name.ends_with(&pattern),
+    )
+}
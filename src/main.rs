@@ -1,33 +1,45 @@
 use std::{
+    collections::HashSet,
+    env,
     fs::{self, File},
     io::Write,
-    path::Path,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
     sync::{Arc, Mutex},
 };
 
-use glob;
 use log::{debug, info, warn};
-use minreq;
 use pbr::ProgressBar;
 use simple_logger::SimpleLogger;
 use threadpool::ThreadPool;
 
+mod accounts;
+mod backup;
+mod classpath;
 mod config;
 use config::Config;
 
+mod github;
+mod init;
+mod jar;
 mod jre;
+mod manifest;
+use manifest::Manifest;
+
+mod outdated;
+mod plugin;
 mod pom;
 mod self_update;
 mod utils;
 
+const CONFIG_PATH: &str = "mirua.toml";
 const MIRAI_PATH: &str = "./content";
 const MIRAI_PLUGINS_PATH: &str = "./plugins";
+const PLUGIN_LIBRARIES_PATH: &str = "./plugin-shared-libraries";
 const JRE_PATH: &str = "./runtime";
-const JAVA_PATH: &str = "./runtime/bin/java";
 const MIRUA_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-fn download_maven(pom: &str, save_path: &'static str) {
+fn download_maven(pom: &str, save_path: &'static str) -> Vec<String> {
     //TODO:save_path换path泛型
     let url = pom;
     let pool = ThreadPool::new(12);
@@ -36,9 +48,11 @@ fn download_maven(pom: &str, save_path: &'static str) {
     info!("需要下载依赖数量：{}", dependencies.len());
 
     let pb = Arc::new(Mutex::new(ProgressBar::new(dependencies.len() as u64)));
+    let downloaded = Arc::new(Mutex::new(Vec::new()));
     for dependency in dependencies {
         let url = dependency.to_jar_url();
         let pb = pb.clone();
+        let downloaded = downloaded.clone();
         pool.execute(move || {
             let content = minreq::get(&url).send().unwrap();
             if content.status_code != 200 {
@@ -46,18 +60,96 @@ fn download_maven(pom: &str, save_path: &'static str) {
             }
             let path = Path::new(&url).file_name().unwrap();
             let mut file = File::create(Path::new(save_path).join(path)).unwrap();
-            file.write(content.as_bytes()).unwrap();
+            file.write_all(content.as_bytes()).unwrap();
+            downloaded
+                .lock()
+                .unwrap()
+                .push(path.to_str().unwrap().to_owned());
             pb.lock().unwrap().inc();
         });
     }
     pool.join();
     pb.lock().unwrap().finish_println("");
+
+    let downloaded = downloaded.lock().unwrap();
+    downloaded.clone()
+}
+
+//删除 mirua 下载过、但已经不在配置中的文件
+fn prune_stale(config: &Config) {
+    let coordinates: HashSet<String> = config
+        .mirai
+        .full
+        .iter()
+        .chain(config.mirai.maven.iter())
+        .map(|(project, version)| format!("{}:{}", project, version))
+        .chain(
+            config
+                .mirai
+                .plugins
+                .iter()
+                .map(|(name, plugin)| plugin.identity(name)),
+        )
+        .collect();
+
+    for dir in [MIRAI_PATH, MIRAI_PLUGINS_PATH] {
+        if !Path::new(dir).is_dir() {
+            continue;
+        }
+        let mut manifest = Manifest::load(dir);
+        manifest.prune(&coordinates);
+        manifest.save();
+    }
+}
+
+//旧版本的 mirua 没有清单，jar 与依赖已经在 content 中时按 POM 找出依赖并记到清单里
+//解析失败时返回 false，下次启动再试
+fn adopt_maven(pom_url: &str, coordinate: &str, manifest: &mut Manifest) -> bool {
+    info!("{} 已经下载但没有记录，按 POM 查找已有的依赖", coordinate);
+    let dependencies = match pom::try_get_dependencies(pom_url) {
+        Ok(x) => x,
+        Err(errors) => {
+            for e in errors {
+                warn!("{}", e);
+            }
+            warn!(
+                "解析 {} 的依赖失败，这次使用 content 中所有未记录的 jar",
+                coordinate
+            );
+            return false;
+        }
+    };
+    for dependency in dependencies {
+        let url = dependency.to_jar_url();
+        let file_name = Path::new(&url).file_name().unwrap().to_str().unwrap();
+        if Path::new(MIRAI_PATH).join(file_name).exists() {
+            manifest.record(file_name, coordinate);
+        }
+    }
+    true
+}
+
+//content 中不在清单里的 jar，按文件名排序
+fn unrecorded_jars(manifest: &Manifest) -> Vec<PathBuf> {
+    let mut jars: Vec<_> = fs::read_dir(MIRAI_PATH)
+        .map(|x| {
+            x.filter_map(|x| x.ok())
+                .map(|x| x.path())
+                .filter(|x| x.extension().is_some_and(|x| x == "jar"))
+                .filter(|x| {
+                    let file_name = x.file_name().unwrap().to_string_lossy();
+                    !manifest.contains(&file_name)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    jars.sort();
+    jars
 }
 
 fn parse_mirai_from_config(project: &str, version: &str) -> (String, String, String) {
-    let r: Vec<_> = project.rsplitn(2, ':').collect();
-    let mut r = r.into_iter().rev();
-    let (group_id, artifact_id) = (r.next().unwrap(), r.next().unwrap());
+    let (group_id, artifact_id) = config::check::parse_coordinate(project)
+        .unwrap_or_else(|| panic!("{} 不是合法的坐标", project));
     (
         group_id.to_owned(),
         artifact_id.to_owned(),
@@ -80,40 +172,134 @@ fn init_log() {
 }
 
 fn main() {
+    //自动更新时用于自检，不能有其它输出
+    if env::args().nth(1).as_deref() == Some("--version") {
+        println!("mirua {}", MIRUA_VERSION);
+        return;
+    }
+
     init_log();
 
     info!("Mirua v{}", MIRUA_VERSION);
 
-    let config = Config::get_config("mirua.toml");
+    //--set key=value 可以出现在任何位置，取出后剩下的才是命令
+    //--accept-defaults 同理
+    let mut args = Vec::new();
+    let mut sets = Vec::new();
+    let mut accept_defaults = false;
+    let mut rest = env::args().skip(1);
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--set" => sets.extend(rest.next()),
+            "--accept-defaults" => accept_defaults = true,
+            _ => args.push(arg),
+        }
+    }
+    let has_flag = |flag: &str| args.iter().any(|x| x == flag);
+
+    //配置文件不存在或有问题时也要能运行
+    match args.first().map(String::as_str) {
+        Some("config") => {
+            config::command(CONFIG_PATH, &sets, &args[1..]);
+            return;
+        }
+        //加上 --accept-defaults 时生成后继续启动
+        Some("init") => {
+            init::init(CONFIG_PATH, &args[1..], accept_defaults);
+            if !accept_defaults {
+                return;
+            }
+        }
+        _ if accept_defaults && !Path::new(CONFIG_PATH).exists() => {
+            init::init(CONFIG_PATH, &[], true);
+        }
+        _ => {}
+    }
+
+    let config = Config::get_config(CONFIG_PATH, &sets);
     debug!("{:?}", config);
 
-    //自动更新时会将旧文件重命名为 "<原文件名>.miruaold"
+    match args.first().map(String::as_str) {
+        None | Some("init") => {}
+        Some("outdated") => {
+            outdated::outdated(&config, &sets, has_flag("--apply"), has_flag("--patch"));
+            return;
+        }
+        Some("plugin") => {
+            plugin::command(&config, &sets, &args[1..]);
+            return;
+        }
+        Some("jre") => {
+            jre::store::command(&config, &args[1..]);
+            return;
+        }
+        Some("backup") => {
+            backup::command(&config, &args[1..]);
+            return;
+        }
+        Some("self-update") => {
+            self_update::self_update(&config.update, has_flag("--check"));
+            return;
+        }
+        Some(x) => {
+            log::error!(
+                "未知命令 {}，可用命令：init plugin outdated backup jre self-update config",
+                x
+            );
+            std::process::exit(1);
+        }
+    }
+
+    //windows 上自动更新时旧版本会被改名为 "<原文件名>.miruaold"，正在运行时删不掉
     //新版本启动时删除
-    glob::glob("*.miruaold")
-        .expect("读取当前目录失败")
-        .for_each(|p| {
-            let p = p.expect("读取当前目录失败");
-            match fs::remove_file(p) {
+    if let Ok(self_path) = env::current_exe() {
+        let old_path = self_path.with_extension("miruaold");
+        if old_path.exists() {
+            match fs::remove_file(old_path) {
                 Ok(()) => info!("检测到旧版本文件并删除"),
-                Err(e) => warn!("删除旧版本文件失败, {}", e.to_string()),
+                Err(e) => warn!("删除旧版本文件失败, {}", e),
             };
-        });
+        }
+    }
 
-    if config.self_update {
-        self_update::self_update();
+    if config.update.enabled {
+        self_update::self_update(&config.update, false);
     }
 
     utils::ensure_dir(MIRAI_PATH);
     utils::ensure_dir(MIRAI_PLUGINS_PATH);
 
-    let java_path = config.jre.path.as_deref().unwrap_or(JAVA_PATH);
-    if !jre::check_jre(java_path) {
-        info!("开始下载 adoptopenjdk_openj9 到当前目录");
-        jre::get_jre(JRE_PATH, config.jre.arch.as_deref());
+    backup::on_version_change(&config);
+    prune_stale(&config);
+    let mut content_manifest = Manifest::load(MIRAI_PATH);
+    let mut plugins_manifest = Manifest::load(MIRAI_PLUGINS_PATH);
+
+    let mut java_path = match config.jre.path.as_deref() {
+        Some(path) => path.to_owned(),
+        None => jre::store::managed_java(&config.jre)
+            .to_string_lossy()
+            .into_owned(),
+    };
+    if !jre::check_jre(&java_path) {
+        match jre::find_system_jre(&config.jre) {
+            Some(path) => java_path = path.to_string_lossy().into_owned(),
+            None => {
+                info!("开始下载 jre 到当前目录");
+                jre::store::install(&config.jre);
+                java_path = jre::store::managed_java(&config.jre)
+                    .to_string_lossy()
+                    .into_owned();
+            }
+        }
     }
 
+    //classpath 按 核心jar -> 其它全打包jar -> maven依赖 的顺序排列
+    let mut full_jars = Vec::new();
+    let mut maven_jars = Vec::new();
+
     //检查maven依赖的jar
     for (project, version) in config.mirai.maven.iter() {
+        let coordinate = format!("{}:{}", project, version);
         let (group_id, artifact_id, version) = parse_mirai_from_config(project, version);
         let jar_url = pom::build_maven_jar_url(&group_id, &artifact_id, &version);
         let pom_url = pom::build_maven_pom_url(&group_id, &artifact_id, &version);
@@ -121,15 +307,33 @@ fn main() {
         let jar_path = Path::new(MIRAI_PATH).join(jar_name);
         if !jar_path.exists() {
             info!("缺少 {}，开始下载", jar_name);
-            download_maven(&pom_url, MIRAI_PATH);
+            for file_name in download_maven(&pom_url, MIRAI_PATH) {
+                content_manifest.record(&file_name, &coordinate);
+            }
+        } else if content_manifest.files_of(&coordinate).is_empty()
+            && !adopt_maven(&pom_url, &coordinate, &mut content_manifest)
+        {
+            //依赖解析失败，这次照旧使用 content 中所有不属于其它项目的 jar
+            maven_jars.extend(unrecorded_jars(&content_manifest));
+            continue;
+        }
+        if jar_path.exists() {
+            content_manifest.record(jar_name, &coordinate);
+            maven_jars.push(jar_path.clone());
+        }
+        for file_name in content_manifest.files_of(&coordinate) {
+            let path = Path::new(MIRAI_PATH).join(file_name);
+            if path != jar_path && path.exists() {
+                maven_jars.push(path);
+            }
         }
     }
 
     //检查全打包jar
     for (project, version) in config.mirai.full.iter() {
+        let coordinate = format!("{}:{}", project, version);
         let (group_id, artifact_id, version) = parse_mirai_from_config(project, version);
-        let jar_url =
-            pom::build_maven_jar_url(&group_id, &artifact_id, &version).replace(".jar", "-all.jar");
+        let jar_url = pom::build_maven_full_jar_url(&group_id, &artifact_id, &version);
         let jar_name = Path::new(&jar_url).file_name().unwrap().to_str().unwrap();
         debug!("jar_name:{}", jar_name);
         let jar_path = Path::new(MIRAI_PATH).join(jar_name);
@@ -138,25 +342,40 @@ fn main() {
             info!("缺少 {}，开始下载", jar_name);
             utils::download_to(&jar_url, MIRAI_PATH);
         }
+        content_manifest.record(jar_name, &coordinate);
+        full_jars.push((artifact_id, jar_path));
     }
+    full_jars.sort_by_key(|(artifact_id, _)| {
+        (
+            !artifact_id.starts_with("mirai-core"),
+            artifact_id.to_owned(),
+        )
+    });
 
     //检查插件
-    for (project, version) in config.mirai.plugins.iter() {
-        let (group_id, artifact_id, version) = parse_mirai_from_config(project, version);
-        let jar_url =
-            pom::build_maven_jar_url(&group_id, &artifact_id, &version);
-        let jar_name = Path::new(&jar_url).file_name().unwrap().to_str().unwrap();
-        debug!("jar_name:{}", jar_name);
-        let jar_path = Path::new(MIRAI_PLUGINS_PATH).join(jar_name);
-        debug!("jar_path:{}", jar_path.display());
-        if !jar_path.exists() {
-            info!("缺少 {}，开始下载", jar_name);
-            utils::download_to(&jar_url, MIRAI_PLUGINS_PATH);
-        }
+    for (name, plugin) in config.mirai.plugins.iter() {
+        plugin::install(&mut plugins_manifest, name, plugin);
     }
+    plugin::libraries::sync(&config);
+    plugin::compat::check(&config);
+
+    content_manifest.save();
+    plugins_manifest.save();
+    jar::check(&config, &content_manifest, &plugins_manifest);
+    accounts::sync(&config);
+
+    //未记录的 jar 可能包含全打包 jar，重复的只保留第一个
+    let mut seen = HashSet::new();
+    let jars: Vec<_> = full_jars
+        .into_iter()
+        .map(|(_, path)| path)
+        .chain(maven_jars)
+        .filter(|x| seen.insert(x.clone()))
+        .collect();
 
-    let mut child = Command::new(java_path)
-        .args(&["-cp", Path::new(MIRAI_PATH).join("*").to_str().unwrap()])
+    let mut command = Command::new(&java_path);
+    classpath::apply(&mut command, Path::new(&java_path), &jars);
+    let mut child = command
         //.arg("-Dorg.jline.terminal.dumb=true")
         //.arg("-Djansi.passthrough=true")
         .arg(config.entrypoint)
@@ -176,14 +395,16 @@ fn main() {
 
     if !bootstrap_commands.is_empty() {
         let child_stdin = child.stdin.as_mut().unwrap();
-        child_stdin.write(bootstrap_commands.as_bytes()).unwrap();
+        child_stdin
+            .write_all(bootstrap_commands.as_bytes())
+            .unwrap();
         child_stdin.flush().unwrap();
     }
 
     match child.wait() {
         Ok(code) => info!("mirai退出，状态码 {}", code),
         Err(e) => {
-            log::error!("子进程异常 {}", e.to_string());
+            log::error!("子进程异常 {}", e);
             child.kill().unwrap();
         }
     }
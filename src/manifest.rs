@@ -0,0 +1,87 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+//记录 mirua 下载了哪些文件，以及它们属于配置中的哪一项
+//不在清单中的文件一律不动
+const MANIFEST_NAME: &str = ".mirua-manifest.toml";
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Manifest {
+    #[serde(skip)]
+    dir: PathBuf,
+    //文件名 -> 配置中的坐标 group:artifact:version，其它来源的插件见 Plugin::identity
+    #[serde(default)]
+    files: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    pub fn load<P: AsRef<Path>>(dir: P) -> Manifest {
+        let dir = dir.as_ref();
+        let mut manifest = match fs::read_to_string(dir.join(MANIFEST_NAME)) {
+            Ok(buf) => toml::from_str::<Manifest>(&buf).unwrap_or_else(|e| {
+                warn!("解析 {} 清单失败，将重新生成，{}", dir.display(), e);
+                Manifest::default()
+            }),
+            Err(_) => Manifest::default(),
+        };
+        manifest.dir = dir.to_owned();
+        manifest
+    }
+
+    pub fn save(&self) {
+        let buf = toml::to_string(self).expect("生成清单失败");
+        fs::write(self.dir.join(MANIFEST_NAME), buf).expect("写入清单失败");
+    }
+
+    pub fn record(&mut self, file_name: &str, coordinate: &str) {
+        self.files
+            .insert(file_name.to_owned(), coordinate.to_owned());
+    }
+
+    pub fn forget(&mut self, file_name: &str) {
+        self.files.remove(file_name);
+    }
+
+    pub fn contains(&self, file_name: &str) -> bool {
+        self.files.contains_key(file_name)
+    }
+
+    //属于该坐标的全部文件，按文件名排序
+    pub fn files_of(&self, coordinate: &str) -> Vec<&str> {
+        self.files
+            .iter()
+            .filter(|(_, x)| x.as_str() == coordinate)
+            .map(|(file_name, _)| file_name.as_str())
+            .collect()
+    }
+
+    //删除不属于当前配置的文件
+    pub fn prune(&mut self, coordinates: &HashSet<String>) {
+        let stale: Vec<_> = self
+            .files
+            .iter()
+            .filter(|(_, coordinate)| !coordinates.contains(*coordinate))
+            .map(|(file_name, _)| file_name.to_owned())
+            .collect();
+
+        for file_name in stale {
+            let path = self.dir.join(&file_name);
+            if path.exists() {
+                if let Err(e) = fs::remove_file(&path) {
+                    warn!("删除 {} 失败，{}", path.display(), e);
+                    continue;
+                }
+                info!("删除不再使用的 {}", path.display());
+            } else {
+                debug!("{} 已经不存在", path.display());
+            }
+            self.files.remove(&file_name);
+        }
+    }
+}
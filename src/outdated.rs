@@ -0,0 +1,130 @@
+use log::info;
+
+use crate::{
+    config::{self, Config, Plugin},
+    parse_mirai_from_config, pom,
+    pom::version,
+    prune_stale, utils, CONFIG_PATH,
+};
+
+struct Entry {
+    section: &'static str,
+    project: String,
+    current: String,
+    patch: Option<String>,
+    latest: Option<String>,
+}
+
+impl Entry {
+    //比当前版本新才算可升级
+    fn target(&self, patch_only: bool) -> Option<&str> {
+        let target = if patch_only {
+            self.patch.as_deref()
+        } else {
+            self.latest.as_deref()
+        }?;
+        if version::compare(target, &self.current).is_gt() {
+            Some(target)
+        } else {
+            None
+        }
+    }
+}
+
+fn collect(config: &Config) -> Vec<Entry> {
+    let mut projects: Vec<_> = config
+        .mirai
+        .full
+        .iter()
+        .map(|x| ("full", x))
+        //只有 maven 上的插件能查询新版本
+        .chain(
+            config
+                .mirai
+                .plugins
+                .iter()
+                .filter_map(|(name, plugin)| match plugin {
+                    Plugin::Maven(version) => Some(("plugins", (name, version))),
+                    _ => None,
+                }),
+        )
+        .collect();
+    projects.sort();
+
+    projects
+        .into_iter()
+        .map(|(section, (project, current))| {
+            let (group_id, artifact_id, _) = parse_mirai_from_config(project, current);
+            let versions = pom::get_versions(&group_id, &artifact_id).unwrap_or_default();
+            //versions 已经从低到高排序，从后往前找第一个符合的
+            let mut releases = versions.iter().rev().filter(|x| version::is_release(x));
+            Entry {
+                section,
+                project: project.to_owned(),
+                current: current.to_owned(),
+                patch: releases
+                    .clone()
+                    .find(|x| version::same_minor(x, current))
+                    .cloned(),
+                latest: releases.next().cloned(),
+            }
+        })
+        .collect()
+}
+
+fn print_table(entries: &[Entry]) {
+    let rows: Vec<[&str; 4]> = entries
+        .iter()
+        .map(|x| {
+            [
+                x.project.as_str(),
+                x.current.as_str(),
+                x.patch.as_deref().unwrap_or("-"),
+                x.latest.as_deref().unwrap_or("-"),
+            ]
+        })
+        .collect();
+    utils::print_table(["项目", "当前", "最新补丁", "最新版本"], &rows);
+}
+
+//sets 为命令行中的 --set，应用更新后重新读取配置时需要
+pub fn outdated(config: &Config, sets: &[String], apply: bool, patch_only: bool) {
+    info!("检查 mirai 与插件的新版本");
+    let entries = collect(config);
+    print_table(&entries);
+
+    let upgrades: Vec<_> = entries
+        .iter()
+        .filter_map(|x| x.target(patch_only).map(|target| (x, target)))
+        .collect();
+
+    if upgrades.is_empty() {
+        info!("全部是最新版本");
+        return;
+    }
+    if !apply {
+        info!("{} 项可升级，使用 --apply 写入配置文件", upgrades.len());
+        return;
+    }
+
+    for section in ["full", "plugins"] {
+        let updates: Vec<_> = upgrades
+            .iter()
+            .filter(|(x, _)| x.section == section)
+            .map(|(x, target)| (x.project.to_owned(), target.to_string()))
+            .collect();
+        match section {
+            _ if updates.is_empty() => {}
+            "full" => config::set_versions(CONFIG_PATH, section, &config.mirai.full, &updates),
+            _ => config::set_versions(CONFIG_PATH, section, &config.mirai.plugins, &updates),
+        }
+    }
+
+    for (entry, target) in upgrades {
+        info!("{} {} -> {}", entry.project, entry.current, target);
+    }
+
+    //旧版本的 jar 留在目录里会被一起加载，删掉
+    prune_stale(&Config::get_config(CONFIG_PATH, sets));
+    info!("配置文件已更新，下次启动时会下载新版本");
+}
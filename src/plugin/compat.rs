@@ -0,0 +1,127 @@
+use std::process;
+
+use log::{error, info, warn};
+
+use super::libraries::Cache;
+use crate::{
+    config::{Config, Plugin},
+    pom::version,
+    utils,
+};
+
+//插件依赖的 mirai 组件 -> [mirai.full] 中提供它的项目
+const PROVIDERS: [(&str, &str); 4] = [
+    ("mirai-console", "net.mamoe:mirai-console"),
+    ("mirai-core-api", "net.mamoe:mirai-core-all"),
+    ("mirai-core", "net.mamoe:mirai-core-all"),
+    ("mirai-core-all", "net.mamoe:mirai-core-all"),
+];
+
+struct Entry {
+    plugin: String,
+    component: String,
+    requirement: String,
+    current: String,
+    //None 表示版本要求无法解析
+    compatible: Option<bool>,
+}
+
+impl Entry {
+    fn state(&self) -> &'static str {
+        match self.compatible {
+            Some(true) => "兼容",
+            Some(false) => "不兼容",
+            None => "无法判断",
+        }
+    }
+}
+
+//只有 maven 上的插件有 POM，其它来源不检查
+fn collect(config: &Config) -> Vec<Entry> {
+    let mut cache = Cache::load();
+    let mut entries = Vec::new();
+    let mut plugins: Vec<_> = config.mirai.plugins.iter().collect();
+    plugins.sort_by_key(|(name, _)| *name);
+    for (name, plugin) in plugins {
+        let plugin_version = match plugin {
+            Plugin::Maven(x) => x,
+            _ => continue,
+        };
+        let (group_id, artifact_id) = name.split_once(':').unwrap();
+        let requirements = match cache.requirements(group_id, artifact_id, plugin_version) {
+            Some(x) => x,
+            None => continue,
+        };
+        for (component, requirement) in requirements {
+            let provider = PROVIDERS
+                .iter()
+                .find(|(x, _)| x == component)
+                .and_then(|(_, x)| config.mirai.full.get(*x));
+            let current = match provider {
+                Some(x) => x,
+                None => continue,
+            };
+            let compatible = if requirement.contains("${") {
+                None
+            } else {
+                version::satisfies(current, requirement)
+            };
+            entries.push(Entry {
+                plugin: name.to_owned(),
+                component: component.to_owned(),
+                requirement: requirement.to_owned(),
+                current: current.to_owned(),
+                compatible,
+            });
+        }
+    }
+    cache.save();
+    entries
+}
+
+//mirua plugin check，输出全部插件的兼容性
+pub fn report(config: &Config) {
+    let entries = collect(config);
+    if entries.is_empty() {
+        info!("没有插件声明了 mirai 的版本要求");
+        return;
+    }
+    let rows: Vec<[&str; 5]> = entries
+        .iter()
+        .map(|x| {
+            [
+                x.plugin.as_str(),
+                x.component.as_str(),
+                x.requirement.as_str(),
+                x.current.as_str(),
+                x.state(),
+            ]
+        })
+        .collect();
+    utils::print_table(["插件", "依赖", "要求", "当前", "结果"], &rows);
+}
+
+//启动前检查，plugin-compatibility 为 block 时有不兼容的插件就不启动
+pub fn check(config: &Config) {
+    let mode = config.plugin_compatibility.to_lowercase();
+    if mode == "off" {
+        return;
+    }
+    let incompatible: Vec<_> = collect(config)
+        .into_iter()
+        .filter(|x| x.compatible == Some(false))
+        .collect();
+    for entry in incompatible.iter() {
+        warn!(
+            "插件 {} 要求 {} {}，当前为 {}",
+            entry.plugin, entry.component, entry.requirement, entry.current
+        );
+    }
+    if mode == "block" && !incompatible.is_empty() {
+        error!(
+            "{} 个插件与当前的 mirai 不兼容，停止启动；仍要启动请把 plugin-compatibility 改为 warn",
+            incompatible.len()
+        );
+        process::exit(1);
+    }
+}
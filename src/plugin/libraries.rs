@@ -0,0 +1,284 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, Plugin},
+    manifest::Manifest,
+    pom::{self, version},
+    utils, PLUGIN_LIBRARIES_PATH,
+};
+
+//解析依赖要逐个请求 POM，结果缓存下来，只有新出现的坐标才需要解析
+const CACHE_NAME: &str = ".mirua-dependencies.toml";
+
+//全打包 jar 中已经包含，POM 里却不一定列出的库
+const PROVIDED_GROUPS: [&str; 3] = ["net.mamoe", "org.jetbrains.kotlin", "org.jetbrains.kotlinx"];
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub(super) struct Cache {
+    //只有 POM 没有 jar 的坐标，例如 parent 和 bom
+    #[serde(default, rename = "pom-only")]
+    pom_only: BTreeSet<String>,
+    //坐标 -> 全部传递依赖，均为 group:artifact:version
+    #[serde(default)]
+    resolved: BTreeMap<String, Vec<String>>,
+    //插件坐标 -> 直接依赖的 mirai 组件及其版本要求，见 compat
+    #[serde(default)]
+    requirements: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Cache {
+    pub(super) fn load() -> Cache {
+        let path = Path::new(PLUGIN_LIBRARIES_PATH).join(CACHE_NAME);
+        match fs::read_to_string(&path) {
+            Ok(buf) => toml::from_str(&buf).unwrap_or_else(|e| {
+                warn!("解析 {} 失败，将重新解析依赖，{}", path.display(), e);
+                Cache::default()
+            }),
+            Err(_) => Cache::default(),
+        }
+    }
+
+    pub(super) fn save(&self) {
+        utils::ensure_dir(PLUGIN_LIBRARIES_PATH);
+        let buf = toml::to_string(self).expect("生成依赖缓存失败");
+        fs::write(Path::new(PLUGIN_LIBRARIES_PATH).join(CACHE_NAME), buf)
+            .expect("写入依赖缓存失败");
+    }
+
+    //解析失败时不缓存，下次启动再试
+    fn resolve(&mut self, group_id: &str, artifact_id: &str, version: &str) -> Option<&[String]> {
+        let coordinate = format!("{}:{}:{}", group_id, artifact_id, version);
+        if !self.resolved.contains_key(&coordinate) {
+            info!("解析 {} 的依赖", coordinate);
+            let url = pom::build_maven_pom_url(group_id, artifact_id, version);
+            let dependencies = match pom::try_get_dependencies(&url) {
+                Ok(x) => x,
+                Err(errors) => {
+                    for e in errors {
+                        warn!("{}", e);
+                    }
+                    warn!("解析 {} 的依赖失败", coordinate);
+                    return None;
+                }
+            };
+            let mut dependencies: Vec<_> = dependencies
+                .into_iter()
+                .filter_map(|x| {
+                    let version = x.version?;
+                    Some(format!("{}:{}:{}", x.group_id, x.artifact_id, version))
+                })
+                .filter(|x| *x != coordinate)
+                .collect();
+            dependencies.sort();
+            self.resolved.insert(coordinate.to_owned(), dependencies);
+        }
+        self.resolved.get(&coordinate).map(|x| x.as_slice())
+    }
+
+    //只记录 net.mamoe 下的直接依赖，获取失败时不缓存，下次再试
+    pub(super) fn requirements(
+        &mut self,
+        group_id: &str,
+        artifact_id: &str,
+        version: &str,
+    ) -> Option<&BTreeMap<String, String>> {
+        let coordinate = format!("{}:{}:{}", group_id, artifact_id, version);
+        if !self.requirements.contains_key(&coordinate) {
+            let requirements = pom::get_direct_dependencies(group_id, artifact_id, version)?
+                .into_iter()
+                .filter(|x| x.group_id == "net.mamoe")
+                .filter_map(|x| Some((x.artifact_id, x.version?)))
+                .collect();
+            self.requirements
+                .insert(coordinate.to_owned(), requirements);
+        }
+        self.requirements.get(&coordinate)
+    }
+}
+
+//"group:artifact:version" -> ("group:artifact", "version")
+fn split_version(coordinate: &str) -> (&str, &str) {
+    coordinate.rsplit_once(':').unwrap()
+}
+
+//下载 jar，没有 jar 的坐标返回 Ok(false)
+fn download(library: &str, jar_url: &str, jar_path: &Path) -> Result<bool, String> {
+    let resp = minreq::get(jar_url)
+        .send()
+        .map_err(|e| format!("请求 {} 失败，{}", jar_url, e))?;
+    if resp.status_code == 404 {
+        debug!("{} 没有 jar", library);
+        return Ok(false);
+    }
+    if resp.status_code != 200 {
+        return Err(format!(
+            "请求 {} 失败，状态码 {}",
+            jar_url, resp.status_code
+        ));
+    }
+    info!("下载依赖 {}", jar_path.display());
+    fs::write(jar_path, resp.as_bytes())
+        .map_err(|e| format!("写入 {} 失败，{}", jar_path.display(), e))?;
+    Ok(true)
+}
+
+//下载 maven 插件的传递依赖到共享库目录，mirai-console 会让所有插件都能加载它们
+//[mirai.full] 已经提供的库跳过，不同插件需要同一个库的不同版本时取最高的版本并提示
+//网络或解析出错只提示，不影响启动，出错时也不删除已有的库
+pub fn sync(config: &Config) {
+    let has_maven = config
+        .mirai
+        .plugins
+        .values()
+        .any(|x| matches!(x, Plugin::Maven(_)));
+    if !has_maven {
+        //没有 maven 插件时不解析任何 POM，只清理以前下载的库
+        if Path::new(PLUGIN_LIBRARIES_PATH).is_dir() {
+            let mut manifest = Manifest::load(PLUGIN_LIBRARIES_PATH);
+            manifest.prune(&HashSet::new());
+            manifest.save();
+        }
+        return;
+    }
+
+    utils::ensure_dir(PLUGIN_LIBRARIES_PATH);
+    let mut cache = Cache::load();
+    let mut used = HashSet::new();
+    let mut complete = true;
+
+    let mut provided = HashMap::new();
+    for (project, version) in config.mirai.full.iter() {
+        let (group_id, artifact_id) = project.split_once(':').unwrap();
+        used.insert(format!("{}:{}", project, version));
+        provided.insert(project.to_owned(), version.to_owned());
+        match cache.resolve(group_id, artifact_id, version) {
+            Some(libraries) => {
+                for library in libraries {
+                    let (project, version) = split_version(library);
+                    provided.insert(project.to_owned(), version.to_owned());
+                }
+            }
+            None => complete = false,
+        }
+    }
+    //不知道 mirai 自带了哪些库时，下载插件依赖可能和 mirai 冲突，这次先不同步
+    if !complete {
+        warn!("无法确定 mirai 自带的库，跳过插件依赖的同步，下次启动时重试");
+        cache.save();
+        return;
+    }
+
+    //库 -> [(版本, 需要它的插件)]
+    let mut wanted: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    let mut plugins: Vec<_> = config.mirai.plugins.iter().collect();
+    plugins.sort_by_key(|(name, _)| *name);
+    for (name, plugin) in plugins {
+        //只有 maven 上的插件有 POM
+        let plugin_version = match plugin {
+            Plugin::Maven(x) => x,
+            _ => continue,
+        };
+        let (group_id, artifact_id) = name.split_once(':').unwrap();
+        used.insert(format!("{}:{}", name, plugin_version));
+        let libraries = match cache.resolve(group_id, artifact_id, plugin_version) {
+            Some(x) => x,
+            None => {
+                warn!("插件 {} 的依赖没有解析完整，可能无法加载", name);
+                complete = false;
+                continue;
+            }
+        };
+        for library in libraries {
+            let (project, version) = split_version(library);
+            let group_id = project.split(':').next().unwrap();
+            if PROVIDED_GROUPS.contains(&group_id) || config.mirai.plugins.contains_key(project) {
+                continue;
+            }
+            if let Some(provided) = provided.get(project) {
+                if provided != version {
+                    warn!(
+                        "插件 {} 需要 {}:{}，mirai 自带的是 {}，将使用自带的版本",
+                        name, project, version, provided
+                    );
+                }
+                continue;
+            }
+            wanted
+                .entry(project.to_owned())
+                .or_default()
+                .push((version.to_owned(), name.to_owned()));
+        }
+    }
+
+    let mut libraries = HashSet::new();
+    for (project, users) in wanted {
+        let version = users
+            .iter()
+            .map(|(version, _)| version)
+            .max_by(|a, b| version::compare(a, b))
+            .unwrap();
+        if users.iter().any(|(x, _)| x != version) {
+            let users: Vec<_> = users
+                .iter()
+                .map(|(version, name)| format!("{} 需要 {}", name, version))
+                .collect();
+            warn!(
+                "{} 的版本冲突：{}，将使用 {}",
+                project,
+                users.join("，"),
+                version
+            );
+        }
+        libraries.insert(format!("{}:{}", project, version));
+    }
+
+    let mut manifest = Manifest::load(PLUGIN_LIBRARIES_PATH);
+    //有插件没解析完整时，它以前用的库可能不在 libraries 中，不能删除
+    if complete {
+        manifest.prune(&libraries);
+    }
+    let mut libraries: Vec<_> = libraries.into_iter().collect();
+    libraries.sort();
+    for library in libraries {
+        if cache.pom_only.contains(&library) {
+            continue;
+        }
+        let mut parts = library.split(':');
+        let (group_id, artifact_id, version) = (
+            parts.next().unwrap(),
+            parts.next().unwrap(),
+            parts.next().unwrap(),
+        );
+        let jar_url = pom::build_maven_jar_url(group_id, artifact_id, version);
+        let jar_name = Path::new(&jar_url).file_name().unwrap().to_str().unwrap();
+        let jar_path = Path::new(PLUGIN_LIBRARIES_PATH).join(jar_name);
+        if !jar_path.exists() {
+            match download(&library, &jar_url, &jar_path) {
+                Ok(true) => {}
+                Ok(false) => {
+                    cache.pom_only.insert(library);
+                    continue;
+                }
+                Err(e) => {
+                    warn!("下载 {} 失败，下次启动时重试，{}", library, e);
+                    continue;
+                }
+            }
+        }
+        manifest.record(jar_name, &library);
+    }
+    manifest.save();
+
+    if complete {
+        cache.resolved.retain(|x, _| used.contains(x));
+        cache.requirements.retain(|x, _| used.contains(x));
+    }
+    cache.save();
+}
@@ -0,0 +1,305 @@
+use std::{fs, path::Path, process};
+
+use log::{error, info, warn};
+
+use crate::{
+    config::{self, check::parse_coordinate, Config, GithubPlugin, Plugin},
+    github,
+    manifest::Manifest,
+    pom::{self, version},
+    prune_stale,
+    utils::{self, fail},
+    CONFIG_PATH, MIRAI_PLUGINS_PATH,
+};
+
+pub mod compat;
+pub mod libraries;
+
+//下载插件到 ./plugins 并记入清单，已经下载过的跳过
+pub fn install(manifest: &mut Manifest, name: &str, plugin: &Plugin) {
+    let identity = plugin.identity(name);
+    let jar_name = match plugin {
+        Plugin::Maven(version) => {
+            let (group_id, artifact_id) =
+                parse_coordinate(name).unwrap_or_else(|| panic!("{} 不是合法的坐标", name));
+            let jar_url = pom::build_maven_jar_url(group_id, artifact_id, version);
+            let jar_name = file_name_of(&jar_url, name);
+            if !Path::new(MIRAI_PLUGINS_PATH).join(&jar_name).exists() {
+                info!("缺少 {}，开始下载", jar_name);
+                utils::download_to(&jar_url, MIRAI_PLUGINS_PATH);
+            }
+            jar_name
+        }
+        Plugin::Url(x) => {
+            let jar_name = file_name_of(&x.url, name);
+            if !Path::new(MIRAI_PLUGINS_PATH).join(&jar_name).exists() {
+                info!("缺少 {}，开始下载", jar_name);
+                fetch(&x.url, &jar_name, x.sha256.as_deref());
+            }
+            jar_name
+        }
+        Plugin::Github(x) => match install_github(manifest, name, x, &identity) {
+            Some(x) => x,
+            None => return,
+        },
+        Plugin::Path(x) => {
+            let source = Path::new(&x.path);
+            let jar_name = file_name_of(&x.path, name);
+            let jar_path = Path::new(MIRAI_PLUGINS_PATH).join(&jar_name);
+            let modified = |x: &Path| x.metadata().and_then(|x| x.modified()).ok();
+            match (modified(source), modified(&jar_path)) {
+                (None, Some(_)) => warn!("找不到 {}，继续使用已有的 {}", x.path, jar_name),
+                (None, None) => panic!("找不到插件 {} 的文件 {}", name, x.path),
+                //源文件比复制过来的新时重新复制
+                (Some(source_time), Some(jar_time)) if source_time <= jar_time => {}
+                _ => {
+                    info!("复制 {} 到 {}", x.path, MIRAI_PLUGINS_PATH);
+                    fs::copy(source, &jar_path)
+                        .unwrap_or_else(|e| panic!("复制 {} 失败，{}", x.path, e));
+                }
+            }
+            jar_name
+        }
+    };
+    manifest.record(&jar_name, &identity);
+}
+
+//去掉查询参数后的文件名，取不到时用插件名
+fn file_name_of(url: &str, name: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    match path.rsplit(['/', '\\']).next() {
+        Some(x) if !x.is_empty() => x.to_owned(),
+        _ => format!("{}.jar", name.replace(':', "-")),
+    }
+}
+
+//先下载到临时文件，校验通过后再放进插件目录，免得 mirai 加载到残缺的 jar
+fn fetch(url: &str, jar_name: &str, sha256: Option<&str>) {
+    let temp_file = tempfile::Builder::new()
+        .prefix(".mirua-plugin-")
+        .tempfile_in(MIRAI_PLUGINS_PATH)
+        .expect("创建临时文件失败");
+    let actual = utils::download_file(url, temp_file.path());
+    match sha256 {
+        Some(expected) if !expected.eq_ignore_ascii_case(&actual) => {
+            panic!(
+                "{} 校验失败，期望 sha256 {}，实际 {}",
+                jar_name, expected, actual
+            )
+        }
+        Some(_) => info!("sha256 校验通过"),
+        None => {}
+    }
+    temp_file
+        .persist(Path::new(MIRAI_PLUGINS_PATH).join(jar_name))
+        .expect("保存插件失败");
+}
+
+//每次启动都查询 release，latest 有新版本时替换掉旧文件
+//查询失败时继续使用已经下载的文件，返回 None 表示保持清单不变
+fn install_github(
+    manifest: &mut Manifest,
+    name: &str,
+    plugin: &GithubPlugin,
+    identity: &str,
+) -> Option<String> {
+    let existing: Vec<String> = manifest
+        .files_of(identity)
+        .into_iter()
+        .filter(|x| Path::new(MIRAI_PLUGINS_PATH).join(x).exists())
+        .map(|x| x.to_owned())
+        .collect();
+    let tag = plugin.version.as_deref().filter(|x| *x != "latest");
+    let release = match github::release(&plugin.github, tag) {
+        Ok(x) => x,
+        Err(e) if !existing.is_empty() => {
+            warn!("{}，继续使用已有的 {}", e, existing.join(" "));
+            return None;
+        }
+        Err(e) => panic!("获取插件 {} 失败，{}", name, e),
+    };
+    let pattern = plugin.asset.as_deref().unwrap_or("*.jar");
+    let pattern = glob::Pattern::new(pattern)
+        .unwrap_or_else(|e| panic!("插件 {} 的 asset \"{}\" 无效，{}", name, pattern, e));
+    let asset = release
+        .assets
+        .iter()
+        .find(|x| pattern.matches(&x.name))
+        .unwrap_or_else(|| {
+            panic!(
+                "{} {} 中没有符合 {} 的文件",
+                plugin.github, release.tag_name, pattern
+            )
+        });
+
+    let jar_name = asset.name.to_owned();
+    if !Path::new(MIRAI_PLUGINS_PATH).join(&jar_name).exists() {
+        info!(
+            "下载 {} {} 中的 {}",
+            plugin.github, release.tag_name, jar_name
+        );
+        let sha256 = plugin.sha256.to_owned().or_else(|| {
+            release
+                .checksum_of(asset)
+                .unwrap_or_else(|e| panic!("获取 {} 的校验值失败，{}", jar_name, e))
+        });
+        fetch(&asset.browser_download_url, &jar_name, sha256.as_deref());
+    }
+    for old in existing.iter().filter(|x| **x != jar_name) {
+        info!("删除旧版本 {}", old);
+        if let Err(e) = fs::remove_file(Path::new(MIRAI_PLUGINS_PATH).join(old)) {
+            warn!("删除 {} 失败，{}", old, e);
+        }
+        manifest.forget(old);
+    }
+    Some(jar_name)
+}
+
+//list 中显示的来源
+fn describe(plugin: &Plugin) -> String {
+    match plugin {
+        Plugin::Maven(version) => version.to_owned(),
+        Plugin::Url(x) => x.url.to_owned(),
+        Plugin::Github(x) => format!(
+            "github {} {}",
+            x.github,
+            x.version.as_deref().unwrap_or("latest")
+        ),
+        Plugin::Path(x) => format!("path {}", x.path),
+    }
+}
+
+//在仓库中查找版本，没有指定版本时取最新的正式版本
+fn resolve(project: &str, version: Option<&str>) -> String {
+    let (group_id, artifact_id) = parse_coordinate(project).unwrap_or_else(|| {
+        fail(format!(
+            "{} 不是合法的坐标，格式为 <groupId>:<artifactId>",
+            project
+        ))
+    });
+    let versions = pom::get_versions(group_id, artifact_id)
+        .unwrap_or_else(|| fail(format!("仓库中找不到 {}", project)));
+    match version {
+        Some(version) if versions.iter().any(|x| x == version) => version.to_owned(),
+        Some(version) => fail(format!(
+            "仓库中没有 {} {}，可用版本：{}",
+            project,
+            version,
+            versions.join(" ")
+        )),
+        None => versions
+            .into_iter()
+            .rev()
+            .find(|x| version::is_release(x))
+            .unwrap_or_else(|| fail(format!("{} 没有正式版本，请用 @<版本> 指定", project))),
+    }
+}
+
+//写入配置文件后重新读取，按新配置下载插件、删除旧文件
+fn sync(sets: &[String]) {
+    let config = Config::get_config(CONFIG_PATH, sets);
+    utils::ensure_dir(MIRAI_PLUGINS_PATH);
+    prune_stale(&config);
+    let mut manifest = Manifest::load(MIRAI_PLUGINS_PATH);
+    for (name, plugin) in config.mirai.plugins.iter() {
+        install(&mut manifest, name, plugin);
+    }
+    manifest.save();
+    libraries::sync(&config);
+}
+
+//mirua plugin add|remove|list|update
+pub fn command(config: &Config, sets: &[String], args: &[String]) {
+    let plugins = &config.mirai.plugins;
+    match args.first().map(String::as_str) {
+        None | Some("list") => {
+            let manifest = Manifest::load(MIRAI_PLUGINS_PATH);
+            let mut names: Vec<_> = plugins.iter().collect();
+            names.sort_by_key(|(name, _)| *name);
+            for (name, plugin) in names {
+                let installed = manifest
+                    .files_of(&plugin.identity(name))
+                    .iter()
+                    .any(|x| Path::new(MIRAI_PLUGINS_PATH).join(x).exists());
+                let state = if installed { "" } else { "  未下载" };
+                println!("{} {}{}", name, describe(plugin), state);
+            }
+        }
+        Some("add") => {
+            let arg = args.get(1).unwrap_or_else(|| {
+                fail("需要指定插件，例如 org.itxtech:mirai-native@2.0.0".to_owned())
+            });
+            let (project, version) = match arg.split_once('@') {
+                Some((project, version)) => (project, Some(version)),
+                None => (arg.as_str(), None),
+            };
+            let version = resolve(project, version);
+            config::edit(CONFIG_PATH, |doc| {
+                let table = config::project_table(doc, "plugins", plugins);
+                config::replace_value(
+                    table.entry(project).or_insert(toml_edit::Item::None),
+                    version.as_str(),
+                );
+            });
+            info!("已添加 {} {}", project, version);
+            sync(sets);
+        }
+        Some("remove") => {
+            let project = args
+                .get(1)
+                .unwrap_or_else(|| fail("需要指定插件，例如 org.itxtech:mirai-native".to_owned()));
+            if !plugins.contains_key(project) {
+                fail(format!("没有配置插件 {}", project));
+            }
+            config::edit(CONFIG_PATH, |doc| {
+                config::project_table(doc, "plugins", plugins);
+                config::remove_entry(doc, &["mirai", "plugins"], project, false);
+            });
+            info!("已移除 {}", project);
+            sync(sets);
+        }
+        //不指定插件时更新全部插件
+        Some("update") => {
+            let mut projects: Vec<_> = match args.len() {
+                1 => plugins.keys().map(String::as_str).collect(),
+                _ => args[1..].iter().map(String::as_str).collect(),
+            };
+            projects.sort();
+            let mut updates = Vec::new();
+            for project in projects {
+                let current = match plugins.get(project) {
+                    Some(Plugin::Maven(x)) => x,
+                    //其它来源的版本由来源本身决定
+                    Some(_) if args.len() == 1 => continue,
+                    Some(_) => {
+                        warn!("{} 不是 maven 上的插件，跳过", project);
+                        continue;
+                    }
+                    None => {
+                        warn!("没有配置插件 {}，跳过", project);
+                        continue;
+                    }
+                };
+                let latest = resolve(project, None);
+                if version::compare(&latest, current).is_gt() {
+                    info!("{} {} -> {}", project, current, latest);
+                    updates.push((project.to_owned(), latest));
+                }
+            }
+            if updates.is_empty() {
+                info!("插件都是最新版本");
+                return;
+            }
+            config::set_versions(CONFIG_PATH, "plugins", plugins, &updates);
+            sync(sets);
+        }
+        Some("check") => compat::report(config),
+        Some(x) => {
+            error!(
+                "未知命令 plugin {}，可用命令：add remove list update check",
+                x
+            );
+            process::exit(1);
+        }
+    }
+}
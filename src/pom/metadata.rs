@@ -0,0 +1,25 @@
+use serde::Deserialize;
+
+#[derive(Debug, Default, Deserialize, PartialEq, Eq, Clone)]
+pub struct Metadata {
+    #[serde(rename = "groupId")]
+    pub group_id: Option<String>,
+    #[serde(rename = "artifactId")]
+    pub artifact_id: Option<String>,
+    pub versioning: Option<Versioning>,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq, Eq, Clone)]
+pub struct Versioning {
+    pub latest: Option<String>,
+    pub release: Option<String>,
+    pub versions: Option<Versions>,
+    #[serde(rename = "lastUpdated")]
+    pub last_updated: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq, Eq, Clone)]
+pub struct Versions {
+    #[serde(rename = "version", default)]
+    pub versions: Vec<String>,
+}
@@ -1,12 +1,14 @@
 use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 
-use log::debug;
-use minreq;
+use log::{debug, warn};
 use quick_xml::de;
 
+pub mod metadata;
 pub mod structure;
+pub mod version;
+use metadata::Metadata;
 use structure::{Dependency, Exclusion, Project};
 
 const MAVEN_URL: &str = "https://maven.aliyun.com/repository/public";
@@ -33,6 +35,88 @@ pub fn build_maven_jar_url(group_id: &str, artifact_id: &str, version: &str) ->
     )
 }
 
+//mirai 的完整打包 jar 带有 -all 后缀
+pub fn build_maven_full_jar_url(group_id: &str, artifact_id: &str, version: &str) -> String {
+    format!(
+        "{}-all.jar",
+        build_maven_base_url(group_id, artifact_id, version)
+    )
+}
+
+pub fn build_maven_metadata_url(group_id: &str, artifact_id: &str) -> String {
+    let prefix = group_id.split('.').collect::<Vec<_>>().join("/");
+    format!(
+        "{}/{}/{}/maven-metadata.xml",
+        MAVEN_URL, prefix, artifact_id
+    )
+}
+
+//获取仓库中该项目的全部版本，按版本号从低到高排序
+pub fn get_versions(group_id: &str, artifact_id: &str) -> Option<Vec<String>> {
+    let url = build_maven_metadata_url(group_id, artifact_id);
+    debug!("获取 {}", url);
+    let resp = match minreq::get(&url).send() {
+        Ok(resp) if resp.status_code == 200 => resp,
+        Ok(resp) => {
+            warn!("获取 {} 失败，状态码 {}", url, resp.status_code);
+            return None;
+        }
+        Err(e) => {
+            warn!("获取 {} 失败，{}", url, e);
+            return None;
+        }
+    };
+    let metadata: Metadata = match resp.as_str().map(de::from_str) {
+        Ok(Ok(metadata)) => metadata,
+        _ => {
+            warn!("解析 {} 失败", url);
+            return None;
+        }
+    };
+
+    let mut versions = metadata
+        .versioning
+        .and_then(|x| x.versions)
+        .map(|x| x.versions)
+        .unwrap_or_default();
+    versions.sort_by(|a, b| version::compare(a, b));
+    versions.dedup();
+    Some(versions)
+}
+
+//POM 中直接声明的依赖，版本保持原样，可能是范围或 ${...} 属性
+pub fn get_direct_dependencies(
+    group_id: &str,
+    artifact_id: &str,
+    version: &str,
+) -> Option<Vec<Dependency>> {
+    let url = build_maven_pom_url(group_id, artifact_id, version);
+    debug!("获取 {}", url);
+    let resp = match minreq::get(&url).send() {
+        Ok(resp) if resp.status_code == 200 => resp,
+        Ok(resp) => {
+            warn!("获取 {} 失败，状态码 {}", url, resp.status_code);
+            return None;
+        }
+        Err(e) => {
+            warn!("获取 {} 失败，{}", url, e);
+            return None;
+        }
+    };
+    match resp.as_str().map(de::from_str::<Project>) {
+        Ok(Ok(project)) => Some(
+            project
+                .dependencies
+                .map(|x| x.dependencies)
+                .unwrap_or_default(),
+        ),
+        _ => {
+            warn!("解析 {} 失败", url);
+            None
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub struct DependencyInfo {
     pub group_id: String,
@@ -48,53 +132,77 @@ impl DependencyInfo {
     }
 }
 
+type Resolved = Arc<RwLock<HashMap<DependencyInfo, Dependency>>>;
+type Errors = Arc<Mutex<Vec<String>>>;
+
 pub fn get_dependencies(url: &str) -> HashSet<Dependency> {
-    let result: HashMap<DependencyInfo, Dependency> = HashMap::new();
-    let result = Arc::new(RwLock::new(result));
-    _get_dependencies(url, HashSet::new(), result.clone());
-    Arc::try_unwrap(result)
+    try_get_dependencies(url).unwrap_or_else(|e| panic!("{}", e.join("，")))
+}
+
+//任何一个 POM 获取或解析失败都返回全部错误，此时已解析出的部分是不完整的
+pub fn try_get_dependencies(url: &str) -> Result<HashSet<Dependency>, Vec<String>> {
+    let result: Resolved = Arc::new(RwLock::new(HashMap::new()));
+    let errors: Errors = Arc::new(Mutex::new(Vec::new()));
+    _get_dependencies(url, HashSet::new(), result.clone(), errors.clone());
+    let errors = Arc::try_unwrap(errors).unwrap().into_inner().unwrap();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(Arc::try_unwrap(result)
         .unwrap()
         .into_inner()
         .unwrap()
         .into_iter()
         .map(|x| x.1)
-        .collect()
+        .collect())
 }
 
-fn _get_dependencies(
-    url: &str,
-    exclusions: HashSet<Exclusion>,
-    result: Arc<RwLock<HashMap<DependencyInfo, Dependency>>>,
-) {
+fn fetch_project(url: &str) -> Result<Project, String> {
     debug!("获取 {}", url);
-    let resp = minreq::get(url)
-        .send()
-        .expect(&format!("获取 {} 失败", url))
-        .as_str()
-        .expect(&format!("获取 {} 失败", url))
-        .to_owned();
-    let project: Project = de::from_str(&resp).expect(&format!("解析 {} 失败", url));
+    let resp = match minreq::get(url).send() {
+        Ok(resp) if resp.status_code == 200 => resp,
+        Ok(resp) => return Err(format!("获取 {} 失败，状态码 {}", url, resp.status_code)),
+        Err(e) => return Err(format!("获取 {} 失败，{}", url, e)),
+    };
+    match resp.as_str().map(de::from_str::<Project>) {
+        Ok(Ok(project)) => Ok(project),
+        _ => Err(format!("解析 {} 失败", url)),
+    }
+}
+
+fn _get_dependencies(url: &str, exclusions: HashSet<Exclusion>, result: Resolved, errors: Errors) {
+    let project = match fetch_project(url) {
+        Ok(x) => x,
+        Err(e) => {
+            errors.lock().unwrap().push(e);
+            return;
+        }
+    };
 
     //获取当前project信息，生成Dependency
 
     let (group_id, artifact_id, version) = match &project.parent {
         Some(parent) => (
-            project
-                .group_id
-                .as_ref()
-                .unwrap_or_else(|| &parent.group_id),
+            Some(project.group_id.as_ref().unwrap_or(&parent.group_id)),
             &project.artifact_id,
-            project
-                .version
-                .as_ref()
-                .unwrap_or_else(|| parent.version.as_ref().unwrap()),
+            project.version.as_ref().or(parent.version.as_ref()),
         ),
         None => (
-            project.group_id.as_ref().unwrap(),
+            project.group_id.as_ref(),
             &project.artifact_id,
-            project.version.as_ref().unwrap(),
+            project.version.as_ref(),
         ),
     };
+    let (group_id, version) = match (group_id, version) {
+        (Some(group_id), Some(version)) => (group_id, version),
+        _ => {
+            errors
+                .lock()
+                .unwrap()
+                .push(format!("{} 中缺少 groupId 或 version", url));
+            return;
+        }
+    };
 
     result.write().unwrap().insert(
         DependencyInfo::new(group_id.to_owned(), artifact_id.to_owned()),
@@ -108,7 +216,12 @@ fn _get_dependencies(
 
     //有parent，parent成为依赖，并抓取parent的子依赖
     if let Some(parent) = &project.parent {
-        _get_dependencies(&parent.to_pom_url(), HashSet::new(), result.clone());
+        _get_dependencies(
+            &parent.to_pom_url(),
+            HashSet::new(),
+            result.clone(),
+            errors.clone(),
+        );
     }
 
     //没子依赖了
@@ -150,7 +263,23 @@ fn _get_dependencies(
 
             //不需要optional的依赖
             if let Some(optional) = x.optional {
-                if optional == true {
+                if optional {
+                    return false;
+                }
+            }
+
+            //版本范围与属性无法直接拼出地址，跳过，可能因此缺少真正需要的库
+            match x.version.as_deref() {
+                Some(v) if !v.starts_with(['[', '(']) && !v.contains("${") => {}
+                v => {
+                    warn!(
+                        "跳过 {}:{} 的依赖 {}:{}，版本 {} 无法解析",
+                        group_id,
+                        artifact_id,
+                        x.group_id,
+                        x.artifact_id,
+                        v.unwrap_or("未指定")
+                    );
                     return false;
                 }
             }
@@ -171,11 +300,19 @@ fn _get_dependencies(
                 })
             }
             let rc = result.clone();
+            let ec = errors.clone();
             thread::spawn(move || {
-                _get_dependencies(&url.to_owned(), candidated_exclusions, rc);
+                _get_dependencies(&url.to_owned(), candidated_exclusions, rc, ec);
             })
         })
         .collect::<Vec<_>>()
         .into_iter()
-        .for_each(|x| x.join().unwrap_or(()));
+        .for_each(|x| {
+            if x.join().is_err() {
+                errors
+                    .lock()
+                    .unwrap()
+                    .push(format!("解析 {} 的依赖时出错", url));
+            }
+        });
 }
@@ -0,0 +1,234 @@
+use std::cmp::Ordering;
+
+//简化版的 maven ComparableVersion
+//1.0 == 1.0.0 == 1.0-ga，1.0-alpha < 1.0-beta < 1.0-M1 < 1.0-RC < 1.0-SNAPSHOT < 1.0 < 1.0-sp < 1.0.1
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item {
+    Int(u64),
+    Str(String),
+}
+
+fn parse_items(version: &str) -> Vec<Item> {
+    let mut items = Vec::new();
+    let mut buf = String::new();
+    let mut is_digit = false;
+
+    let mut flush = |buf: &mut String, is_digit: bool| {
+        if buf.is_empty() {
+            return;
+        }
+        let item = if is_digit {
+            Item::Int(buf.parse().unwrap_or(u64::MAX))
+        } else {
+            //限定符前多余的 0 去掉，2.0.0-beta 等同于 2-beta
+            while items.last() == Some(&Item::Int(0)) {
+                items.pop();
+            }
+            Item::Str(buf.to_lowercase())
+        };
+        items.push(item);
+        buf.clear();
+    };
+
+    for c in version.chars() {
+        if c == '.' || c == '-' || c == '_' {
+            flush(&mut buf, is_digit);
+            continue;
+        }
+        //数字与字母交界处也算分隔，1.0RC1 -> 1 0 rc 1
+        if !buf.is_empty() && c.is_ascii_digit() != is_digit {
+            flush(&mut buf, is_digit);
+        }
+        is_digit = c.is_ascii_digit();
+        buf.push(c);
+    }
+    flush(&mut buf, is_digit);
+
+    while items.last() == Some(&Item::Int(0)) {
+        items.pop();
+    }
+    items
+}
+
+fn qualifier_rank(qualifier: &str) -> u8 {
+    match qualifier {
+        "alpha" | "a" => 1,
+        "beta" | "b" => 2,
+        "milestone" | "m" => 3,
+        "rc" | "cr" => 4,
+        "snapshot" => 5,
+        "" | "ga" | "final" | "release" => 6,
+        "sp" => 7,
+        _ => 0,
+    }
+}
+
+fn cmp_qualifier(a: &str, b: &str) -> Ordering {
+    let (rank_a, rank_b) = (qualifier_rank(a), qualifier_rank(b));
+    //不认识的限定符（dev、shadow 之类）一律视为预发布，之间按字典序
+    if rank_a == 0 && rank_b == 0 {
+        a.cmp(b)
+    } else {
+        rank_a.cmp(&rank_b)
+    }
+}
+
+fn cmp_item(a: Option<&Item>, b: Option<&Item>) -> Ordering {
+    match (a, b) {
+        (Some(Item::Int(a)), Some(Item::Int(b))) => a.cmp(b),
+        (Some(Item::Str(a)), Some(Item::Str(b))) => cmp_qualifier(a, b),
+        (Some(Item::Int(_)), Some(Item::Str(_))) => Ordering::Greater,
+        (Some(Item::Str(_)), Some(Item::Int(_))) => Ordering::Less,
+        (Some(Item::Int(a)), None) => a.cmp(&0),
+        (None, Some(Item::Int(b))) => 0.cmp(b),
+        (Some(Item::Str(a)), None) => cmp_qualifier(a, ""),
+        (None, Some(Item::Str(b))) => cmp_qualifier("", b),
+        (None, None) => Ordering::Equal,
+    }
+}
+
+pub fn compare(a: &str, b: &str) -> Ordering {
+    let (a, b) = (parse_items(a), parse_items(b));
+    for i in 0..a.len().max(b.len()) {
+        match cmp_item(a.get(i), b.get(i)) {
+            Ordering::Equal => continue,
+            x => return x,
+        }
+    }
+    Ordering::Equal
+}
+
+//不带任何预发布限定符的版本
+pub fn is_release(version: &str) -> bool {
+    parse_items(version).iter().all(|x| match x {
+        Item::Int(_) => true,
+        Item::Str(s) => qualifier_rank(s) >= 6,
+    })
+}
+
+//主版本号与次版本号相同，即同一条补丁线
+pub fn same_minor(a: &str, b: &str) -> bool {
+    //末尾的 0 在解析时被去掉了，这里补回来
+    let prefix = |v: &str| {
+        let mut prefix: Vec<_> = parse_items(v)
+            .into_iter()
+            .take_while(|x| matches!(x, Item::Int(_)))
+            .take(2)
+            .collect();
+        prefix.resize(2, Item::Int(0));
+        prefix
+    };
+    prefix(a) == prefix(b)
+}
+
+//单个范围，例如 [1.0,2.0)、(,2.0]、[1.0]
+fn in_range(version: &str, range: &str) -> Option<bool> {
+    let lower_inclusive = range.starts_with('[');
+    let upper_inclusive = range.ends_with(']');
+    let inner = range.get(1..range.len() - 1)?;
+    let (lower, upper) = match inner.split_once(',') {
+        Some((lower, upper)) => (lower.trim(), upper.trim()),
+        None if lower_inclusive && upper_inclusive => {
+            return Some(compare(version, inner.trim()).is_eq())
+        }
+        None => return None,
+    };
+    let above = match compare(version, lower) {
+        _ if lower.is_empty() => true,
+        Ordering::Greater => true,
+        Ordering::Equal => lower_inclusive,
+        Ordering::Less => false,
+    };
+    let below = match compare(version, upper) {
+        _ if upper.is_empty() => true,
+        Ordering::Less => true,
+        Ordering::Equal => upper_inclusive,
+        Ordering::Greater => false,
+    };
+    Some(above && below)
+}
+
+fn major(version: &str) -> Option<u64> {
+    match parse_items(version).first() {
+        Some(Item::Int(x)) => Some(*x),
+        _ => None,
+    }
+}
+
+//是否满足 maven 的版本要求，多个范围用逗号连接，满足其一即可
+//不带括号的版本只是推荐版本，这里按同一主版本且不低于它处理
+//要求无法解析时返回 None
+pub fn satisfies(version: &str, requirement: &str) -> Option<bool> {
+    let requirement = requirement.trim();
+    if !requirement.starts_with(['[', '(']) {
+        return Some(
+            major(version)? == major(requirement)? && compare(version, requirement).is_ge(),
+        );
+    }
+    let mut rest = requirement;
+    let mut satisfied = false;
+    while !rest.is_empty() {
+        let end = rest.find([']', ')'])?;
+        satisfied |= in_range(version, rest[..=end].trim())?;
+        rest = rest[end + 1..]
+            .trim_start()
+            .trim_start_matches(',')
+            .trim_start();
+    }
+    Some(satisfied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_items() {
+        assert_eq!(compare("0.1.11", "0.1.2"), Ordering::Greater);
+        assert_eq!(compare("2.10.0", "2.9.3"), Ordering::Greater);
+        assert_eq!(compare("1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare("1.0-ga", "1.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn qualifiers() {
+        let ordered = [
+            "2.0-alpha",
+            "2.0-beta",
+            "2.0-M1",
+            "2.0-RC",
+            "2.0-RC2",
+            "2.0-SNAPSHOT",
+            "2.0",
+            "2.0-sp",
+            "2.0.1",
+        ];
+        for pair in ordered.windows(2) {
+            assert_eq!(compare(pair[0], pair[1]), Ordering::Less, "{:?}", pair);
+        }
+        assert!(is_release("2.0.0"));
+        assert!(!is_release("2.0.0-RC"));
+        assert!(!is_release("2.0.0-SNAPSHOT"));
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(satisfies("2.0.0", "[2.0,3.0)"), Some(true));
+        assert_eq!(satisfies("2.99.1", "[2.0,3.0)"), Some(true));
+        assert_eq!(satisfies("3.0.0", "[2.0,3.0)"), Some(false));
+        assert_eq!(satisfies("1.9.9", "[2.0,3.0)"), Some(false));
+        assert_eq!(satisfies("3.0.0-RC", "[2.0,3.0)"), Some(true));
+        assert_eq!(satisfies("1.5", "(,1.0],[1.2,)"), Some(true));
+        assert_eq!(satisfies("1.1", "(,1.0],[1.2,)"), Some(false));
+        assert_eq!(satisfies("2.10.0", "[2.10.0]"), Some(true));
+        assert_eq!(satisfies("2.0", "[2.0,3.0"), None);
+    }
+
+    #[test]
+    fn soft_requirement() {
+        assert_eq!(satisfies("2.11.1", "2.10.0"), Some(true));
+        assert_eq!(satisfies("2.9.0", "2.10.0"), Some(false));
+        assert_eq!(satisfies("3.0.0", "2.10.0"), Some(false));
+    }
+}
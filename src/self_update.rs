@@ -1,66 +1,637 @@
+use std::collections::HashMap;
 use std::env;
-use std::fs::{self, File};
-use std::io::Write;
+use std::fs;
+use std::io::{Read, Write};
 use std::path::Path;
-use std::process;
+use std::process::{self, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use ed25519_compact::{PublicKey, Signature};
 use log::{debug, info, warn};
-use minreq;
+use semver::Version;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 
-use crate::MIRUA_VERSION;
+use crate::{config::Update, MIRUA_VERSION};
+
+//发布的每个二进制文件都附带 <文件名>.sig，为 Ed25519 对整个文件的签名（64 字节原始格式）
+//公钥为 64 位十六进制，构建时由环境变量 MIRUA_UPDATE_PUBLIC_KEY 传入，见 README 的“发布更新”
+//没有内置公钥也没有配置 public-key 时不做自动更新
+const UPDATE_PUBLIC_KEY: Option<&str> = option_env!("MIRUA_UPDATE_PUBLIC_KEY");
+
+const fn is_hex_key(key: &str) -> bool {
+    let key = key.as_bytes();
+    if key.len() != 64 {
+        return false;
+    }
+    let mut i = 0;
+    while i < key.len() {
+        if !key[i].is_ascii_hexdigit() {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+//传错了公钥时构建失败，而不是发布后所有更新都校验不过
+const _: () = assert!(
+    match UPDATE_PUBLIC_KEY {
+        Some(key) => is_hex_key(key),
+        None => true,
+    },
+    "MIRUA_UPDATE_PUBLIC_KEY 必须是 64 位十六进制的 Ed25519 公钥"
+);
+
+//从这个版本开始支持 --version，更早的版本会忽略参数直接启动 mirai，无法自检
+const SELF_TEST_SINCE: &str = "0.3.0";
+//自检的最长等待时间
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+const DEFAULT_PACKAGE_API: &str = "https://data.jsdelivr.com/v1/package/gh/zkonge/mirua";
+const DEFAULT_DOWNLOAD_URL: &str = "https://cdn.jsdelivr.net/gh/zkonge/mirua-update";
 
 #[derive(Debug, Deserialize)]
 struct RepoSchema {
-    tags: Vec<String>,
     versions: Vec<String>,
 }
 
-pub fn self_update() {
-    info!("自动更新检测开始");
-    let resp = minreq::get("https://data.jsdelivr.com/v1/package/gh/zkonge/mirua").send();
-    if resp.is_err() || resp.as_ref().unwrap().status_code != 200 {
-        warn!("连接更新服务器失败");
-        return;
+//自建更新源的清单格式，见 README
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    releases: Vec<Release>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    version: String,
+    #[serde(default)]
+    notes: Option<String>,
+    //键为 <os>_<arch>，与 std::env::consts 一致，例如 linux_x86_64
+    targets: HashMap<String, Target>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Target {
+    url: String,
+    sha256: Option<String>,
+    //默认为 <url>.sig
+    signature: Option<String>,
+}
+
+fn target_name() -> String {
+    format!("{}_{}", env::consts::OS, env::consts::ARCH)
+}
+
+//清单中的相对路径相对于清单所在目录
+fn resolve_url(base: &str, url: &str) -> String {
+    if url.contains("://") {
+        return url.to_owned();
+    }
+    let base = &base[..base.rfind('/').map_or(0, |x| x + 1)];
+    format!("{}{}", base, url.trim_start_matches('/'))
+}
+
+fn get_manifest(url: &str) -> Result<Manifest, String> {
+    let resp = minreq::get(url)
+        .with_header("User-Agent", "mirua")
+        .send()
+        .map_err(|e| format!("请求 {} 失败，{}", url, e))?;
+    if resp.status_code != 200 {
+        return Err(format!("请求 {} 失败，状态码 {}", url, resp.status_code));
+    }
+    let mut manifest = resp
+        .json::<Manifest>()
+        .map_err(|e| format!("解析 {} 失败，{}", url, e))?;
+    for target in manifest
+        .releases
+        .iter_mut()
+        .flat_map(|x| x.targets.values_mut())
+    {
+        target.url = resolve_url(url, &target.url);
+        target.signature = target.signature.as_deref().map(|x| resolve_url(url, x));
+    }
+    Ok(manifest)
+}
+
+//没有配置 manifest 时使用 jsDelivr 上的官方仓库，没有 sha256 与更新说明
+fn default_manifest() -> Result<Manifest, String> {
+    let resp = minreq::get(DEFAULT_PACKAGE_API)
+        .send()
+        .map_err(|e| format!("请求 {} 失败，{}", DEFAULT_PACKAGE_API, e))?;
+    if resp.status_code != 200 {
+        return Err(format!(
+            "请求 {} 失败，状态码 {}",
+            DEFAULT_PACKAGE_API, resp.status_code
+        ));
     }
-    let resp = resp.unwrap();
-    let result = resp.json::<RepoSchema>().unwrap();
+    let result = resp
+        .json::<RepoSchema>()
+        .map_err(|e| format!("解析 {} 失败，{}", DEFAULT_PACKAGE_API, e))?;
     debug!("{:?}", result);
 
-    //TODO:解析version，避免0.1.2>0.1.11的情况（版本暂时爬不到那么高）
-    let latest_version = result.versions.iter().max().unwrap();
+    let releases = result
+        .versions
+        .into_iter()
+        .map(|version| {
+            let target = Target {
+                url: format!(
+                    "{}/v{}/mirua_{}",
+                    DEFAULT_DOWNLOAD_URL,
+                    version.trim_start_matches('v'),
+                    target_name()
+                ),
+                sha256: None,
+                signature: None,
+            };
+            Release {
+                targets: vec![(target_name(), target)].into_iter().collect(),
+                notes: None,
+                version,
+            }
+        })
+        .collect();
+    Ok(Manifest { releases })
+}
+
+//只考虑提供了本平台文件的版本
+fn versions_for(manifest: &Manifest, target_name: &str) -> Vec<String> {
+    manifest
+        .releases
+        .iter()
+        .filter(|x| x.targets.contains_key(target_name))
+        .map(|x| x.version.to_owned())
+        .collect()
+}
+
+fn download(url: &str) -> Result<Vec<u8>, String> {
+    match minreq::get(url).with_header("User-Agent", "mirua").send() {
+        Ok(resp) if resp.status_code == 200 => Ok(resp.into_bytes()),
+        Ok(resp) => Err(format!("下载 {} 失败，状态码 {}", url, resp.status_code)),
+        Err(e) => Err(format!("下载 {} 失败，{}", url, e)),
+    }
+}
+
+fn parse_version(version: &str) -> Option<Version> {
+    Version::parse(version.trim_start_matches('v')).ok()
+}
+
+//按配置挑选要更新到的版本，没有可更新的返回 None
+fn select_version(versions: &[String], config: &Update) -> Option<Version> {
+    let current = Version::parse(MIRUA_VERSION).unwrap();
+    let self_test_since = Version::parse(SELF_TEST_SINCE).unwrap();
+    let mut versions: Vec<_> = versions.iter().filter_map(|x| parse_version(x)).collect();
+    versions.sort();
+
+    //固定版本时，不管新旧都切换过去，但不能切换到无法自检的版本
+    if let Some(pin) = config.pin.as_deref() {
+        let pin = parse_version(pin).unwrap_or_else(|| panic!("无法解析 pin 版本 {}", pin));
+        if pin < self_test_since {
+            warn!(
+                "v{} 不支持 --version 自检，pin 只能固定到 v{} 及以后的版本",
+                pin, self_test_since
+            );
+            return None;
+        }
+        if !versions.contains(&pin) {
+            warn!("更新服务器上没有 v{}", pin);
+            return None;
+        }
+        return Some(pin).filter(|x| *x != current);
+    }
+
+    let beta = match config.channel.as_deref() {
+        None | Some("stable") => false,
+        Some("beta") => true,
+        Some(x) => panic!("未知的更新通道 {}，可选 stable beta", x),
+    };
+    let max_version = config
+        .max_version
+        .as_deref()
+        .map(|x| parse_version(x).unwrap_or_else(|| panic!("无法解析 max-version {}", x)));
+
+    //versions 已排序，从后往前找第一个符合条件的
+    versions
+        .into_iter()
+        .rfind(|x| (beta || x.pre.is_empty()) && max_version.as_ref().is_none_or(|max| x <= max))
+        .filter(|x| *x > current && *x >= self_test_since)
+}
+
+fn parse_public_key(hex: &str) -> Option<PublicKey> {
+    if !is_hex_key(hex) {
+        return None;
+    }
+    let bytes: Option<Vec<u8>> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect();
+    PublicKey::from_slice(&bytes?).ok()
+}
+
+//配置中的 public-key 优先，自建更新源用自己的密钥签名
+fn public_key(config: &Update) -> Option<&str> {
+    config.public_key.as_deref().or(UPDATE_PUBLIC_KEY)
+}
+
+fn verify_signature(data: &[u8], signature: &[u8], public_key: &str) -> bool {
+    let public_key = match parse_public_key(public_key) {
+        Some(x) => x,
+        None => {
+            warn!("无法解析 public-key {}", public_key);
+            return false;
+        }
+    };
+    match Signature::from_slice(signature) {
+        Ok(signature) => public_key.verify(data, &signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+//check 为 true 时只报告，不下载
+pub fn self_update(config: &Update, check: bool) {
+    //没有公钥时无法校验任何更新，不必连接更新服务器
+    let public_key = match public_key(config) {
+        Some(x) => x,
+        None => {
+            warn!("构建时没有内置更新公钥，也没有在 [update] 中配置 public-key，自动更新已关闭");
+            return;
+        }
+    };
+    info!("自动更新检测开始");
+    let manifest = match config.manifest.as_deref() {
+        Some(url) => get_manifest(url),
+        None => default_manifest(),
+    };
+    let manifest = match manifest {
+        Ok(x) => x,
+        Err(e) => {
+            warn!("连接更新服务器失败，{}", e);
+            return;
+        }
+    };
+
+    let target_name = target_name();
+    let versions = versions_for(&manifest, &target_name);
 
-    info!("最新版本 v{}", latest_version);
     info!("本地版本 v{}", MIRUA_VERSION);
+    let latest_version = match select_version(&versions, config) {
+        Some(x) => x,
+        None => {
+            info!("不需要更新");
+            return;
+        }
+    };
+    info!("可更新到 v{}", latest_version);
+    let release = manifest
+        .releases
+        .iter()
+        .find(|x| {
+            x.targets.contains_key(&target_name)
+                && parse_version(&x.version).as_ref() == Some(&latest_version)
+        })
+        .unwrap();
+    if let Some(notes) = release.notes.as_deref() {
+        info!("更新说明：\n{}", notes.trim_end());
+    }
 
-    if latest_version.as_str() <= MIRUA_VERSION {
-        info!("不需要更新");
+    if check {
         return;
     }
 
-    let resp = minreq::get(&format!(
-        "https://cdn.jsdelivr.net/gh/zkonge/mirua-update/v{}/mirua_{}_{}",
-        latest_version,
-        env::consts::OS,
-        env::consts::ARCH
-    ))
-    .send();
+    let target = &release.targets[&target_name];
+    let result = match download(&target.url) {
+        Ok(x) => x,
+        Err(e) => {
+            warn!("下载更新失败，{}", e);
+            return;
+        }
+    };
 
-    if resp.is_err() || resp.as_ref().unwrap().status_code != 200 {
-        warn!("下载更新失败");
-        return;
+    if let Some(expected) = target.sha256.as_deref() {
+        let sha256: String = Sha256::digest(&result)
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect();
+        if !expected.eq_ignore_ascii_case(&sha256) {
+            warn!(
+                "更新文件校验失败，期望 sha256 {}，实际 {}，拒绝更新",
+                expected, sha256
+            );
+            return;
+        }
     }
-    let resp = resp.unwrap();
-    let result = resp.into_bytes();
 
-    let self_path = env::args().next().unwrap();
-    let self_path = Path::new(&self_path);
-    fs::rename(self_path, self_path.with_extension("miruaold")).expect("改名失败");
+    //没有签名或签名不对都拒绝更新，CDN 上的文件不可信
+    let signature_url = match target.signature.as_deref() {
+        Some(x) => x.to_owned(),
+        None => format!("{}.sig", target.url),
+    };
+    let signature = match download(&signature_url) {
+        Ok(x) => x,
+        Err(e) => {
+            warn!("下载签名失败，拒绝更新，{}", e);
+            return;
+        }
+    };
+    if !verify_signature(&result, &signature, public_key) {
+        warn!("更新文件签名校验失败，拒绝更新");
+        return;
+    }
+    info!("签名校验通过");
 
-    let mut f = File::create(self_path).unwrap();
-    f.write(&result).unwrap();
+    if let Err(e) = replace_self(&result, &latest_version) {
+        warn!("更新失败，已恢复旧版本，{}", e);
+        return;
+    }
 
     info!("更新完成，重新运行软件即可体验新版");
 
     process::exit(0);
 }
+
+//运行新版本的 --version，输出的版本号对得上才算成功，超时则结束新版本
+fn self_test(path: &Path, version: &Version, timeout: Duration) -> Result<(), String> {
+    let mut child = Command::new(path)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("无法运行新版本，{}", e))?;
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(50)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("新版本自检超过 {} 秒没有结束", timeout.as_secs()));
+            }
+            Err(e) => return Err(format!("等待新版本自检失败，{}", e)),
+        }
+    };
+
+    let mut stdout = String::new();
+    if let Some(mut x) = child.stdout.take() {
+        let _ = x.read_to_string(&mut stdout);
+    }
+    if status.success() && stdout.split_whitespace().last() == Some(&version.to_string()) {
+        Ok(())
+    } else {
+        Err(format!("新版本自检失败，输出：{}", stdout.trim()))
+    }
+}
+
+fn rollback(self_path: &Path, backup_path: &Path) {
+    //windows 上新版本没有在运行，可以直接删除；unix 上 rename 会直接覆盖
+    if cfg!(windows) {
+        let _ = fs::remove_file(self_path);
+    }
+    if let Err(e) = fs::rename(backup_path, self_path) {
+        log::error!(
+            "恢复旧版本失败，请手动将 {} 改名为 {}，{}",
+            backup_path.display(),
+            self_path.display(),
+            e
+        );
+    }
+}
+
+//新版本先写入同目录下的临时文件，替换当前程序后运行自检，失败则回滚
+fn replace_self(data: &[u8], version: &Version) -> Result<(), String> {
+    let self_path = env::current_exe()
+        .and_then(fs::canonicalize)
+        .map_err(|e| format!("获取程序路径失败，{}", e))?;
+    let dir = self_path.parent().ok_or("获取程序所在目录失败")?;
+    let backup_path = self_path.with_extension("miruaold");
+
+    let mut temp = tempfile::Builder::new()
+        .prefix(".mirua-update-")
+        .tempfile_in(dir)
+        .map_err(|e| format!("创建临时文件失败，{}", e))?;
+    temp.write_all(data)
+        .map_err(|e| format!("写入临时文件失败，{}", e))?;
+    //沿用当前程序的权限，unix 上才有可执行位
+    let permissions = fs::metadata(&self_path)
+        .map_err(|e| format!("读取程序权限失败，{}", e))?
+        .permissions();
+    fs::set_permissions(temp.path(), permissions).map_err(|e| format!("设置权限失败，{}", e))?;
+
+    //unix 可以直接覆盖正在运行的程序，windows 只能先把自己改名
+    if cfg!(windows) {
+        fs::rename(&self_path, &backup_path)
+    } else {
+        fs::copy(&self_path, &backup_path).map(|_| ())
+    }
+    .map_err(|e| format!("备份旧版本失败，{}", e))?;
+
+    if let Err(e) = temp.persist(&self_path) {
+        rollback(&self_path, &backup_path);
+        return Err(format!("替换程序失败，{}", e));
+    }
+
+    if let Err(e) = self_test(&self_path, version, SELF_TEST_TIMEOUT) {
+        rollback(&self_path, &backup_path);
+        return Err(e);
+    }
+
+    //windows 上旧版本仍在运行，删不掉，留到下次启动时删除
+    let _ = fs::remove_file(&backup_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(channel: Option<&str>, pin: Option<&str>, max_version: Option<&str>) -> Update {
+        Update {
+            enabled: true,
+            channel: channel.map(String::from),
+            pin: pin.map(String::from),
+            max_version: max_version.map(String::from),
+            manifest: None,
+            public_key: None,
+        }
+    }
+
+    //比当前版本新得多，版本号变动时也不受影响
+    fn versions() -> Vec<String> {
+        [
+            "v0.1.0",
+            "v99.0.0",
+            "v99.1.0-beta.1",
+            "v99.0.1",
+            "v100.0.0-rc.1",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    fn select(config: &Update) -> Option<String> {
+        select_version(&versions(), config).map(|x| x.to_string())
+    }
+
+    #[test]
+    fn channels() {
+        assert_eq!(select(&update(None, None, None)).as_deref(), Some("99.0.1"));
+        assert_eq!(
+            select(&update(Some("stable"), None, None)).as_deref(),
+            Some("99.0.1")
+        );
+        assert_eq!(
+            select(&update(Some("beta"), None, None)).as_deref(),
+            Some("100.0.0-rc.1")
+        );
+    }
+
+    #[test]
+    fn max_version() {
+        assert_eq!(
+            select(&update(None, None, Some("99.0.0"))).as_deref(),
+            Some("99.0.0")
+        );
+        assert_eq!(
+            select(&update(Some("beta"), None, Some("99.5.0"))).as_deref(),
+            Some("99.1.0-beta.1")
+        );
+        assert_eq!(select(&update(None, None, Some(MIRUA_VERSION))), None);
+    }
+
+    #[test]
+    fn pin() {
+        //固定版本不受通道与 max-version 限制
+        assert_eq!(
+            select(&update(None, Some("v99.1.0-beta.1"), Some("99.0.0"))).as_deref(),
+            Some("99.1.0-beta.1")
+        );
+        assert_eq!(select(&update(None, Some("98.0.0"), None)), None);
+        //不支持 --version 的旧版本无法自检，不能固定过去
+        assert_eq!(select(&update(None, Some("0.1.0"), None)), None);
+    }
+
+    //模拟不认识 --version、直接启动的旧版本
+    #[cfg(unix)]
+    #[test]
+    fn self_test_timeout() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mirua");
+        fs::write(&path, "#!/bin/sh\nsleep 30\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let started = Instant::now();
+        let result = self_test(&path, &Version::new(99, 0, 0), Duration::from_secs(1));
+        assert!(result.unwrap_err().contains("没有结束"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        fs::write(&path, "#!/bin/sh\necho mirua 99.0.0\n").unwrap();
+        assert!(self_test(&path, &Version::new(99, 0, 0), Duration::from_secs(10)).is_ok());
+        assert!(self_test(&path, &Version::new(99, 0, 1), Duration::from_secs(10)).is_err());
+    }
+
+    #[test]
+    fn signatures() {
+        use ed25519_compact::{KeyPair, Seed};
+
+        let key_pair = KeyPair::from_seed(Seed::new([7; 32]));
+        let public_key: String = key_pair.pk.iter().map(|x| format!("{:02x}", x)).collect();
+        let data = b"mirua_linux_x86_64";
+        let signature = key_pair.sk.sign(data, None);
+
+        assert!(verify_signature(data, signature.as_ref(), &public_key));
+        assert!(!verify_signature(
+            b"mirua_linux_x86_64 ",
+            signature.as_ref(),
+            &public_key
+        ));
+        assert!(!verify_signature(data, &signature[..63], &public_key));
+        let other = KeyPair::from_seed(Seed::new([8; 32]));
+        let other: String = other.pk.iter().map(|x| format!("{:02x}", x)).collect();
+        assert!(!verify_signature(data, signature.as_ref(), &other));
+        assert!(!verify_signature(data, signature.as_ref(), "not a key"));
+    }
+
+    #[test]
+    fn resolve_urls() {
+        let base = "https://example.com/mirua/manifest.json";
+        assert_eq!(
+            resolve_url(base, "https://cdn.example.com/v0.3.0/mirua_linux_x86_64"),
+            "https://cdn.example.com/v0.3.0/mirua_linux_x86_64"
+        );
+        assert_eq!(
+            resolve_url(base, "v0.3.0/mirua_linux_x86_64"),
+            "https://example.com/mirua/v0.3.0/mirua_linux_x86_64"
+        );
+        //开头的 / 同样相对清单所在目录
+        assert_eq!(
+            resolve_url(base, "/v0.3.0/mirua_linux_x86_64"),
+            "https://example.com/mirua/v0.3.0/mirua_linux_x86_64"
+        );
+    }
+
+    //用本地的 HTTP 服务代替自建更新源，清单为 README 中的示例
+    #[test]
+    fn readme_manifest() {
+        use std::io::{BufRead, BufReader};
+        use std::net::TcpListener;
+
+        let body = include_str!("testdata/manifest.json");
+        assert!(include_str!("../README.md").contains(body));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        let url = format!("http://127.0.0.1:{}/mirua/manifest.json", port);
+        let manifest = get_manifest(&url).unwrap();
+        server.join().unwrap();
+
+        let release = &manifest.releases[0];
+        assert_eq!(release.notes.as_deref(), Some("更新说明，可选"));
+        let linux = &release.targets["linux_x86_64"];
+        assert_eq!(
+            linux.url,
+            format!("http://127.0.0.1:{}/mirua/v0.3.0/mirua_linux_x86_64", port)
+        );
+        assert_eq!(linux.sha256.as_deref(), Some("可选，文件的 sha256"));
+        assert_eq!(
+            release.targets["windows_x86_64"].url,
+            "https://example.com/mirua/v0.3.0/mirua_windows_x86_64.exe"
+        );
+
+        assert_eq!(versions_for(&manifest, "linux_x86_64"), ["0.3.0"]);
+        assert!(versions_for(&manifest, "macos_aarch64").is_empty());
+
+        //0.3.1 没有 windows 的文件，windows 上不会选中
+        let mut manifest = manifest;
+        let mut release = Release {
+            version: "0.3.1".to_owned(),
+            notes: None,
+            targets: HashMap::new(),
+        };
+        let linux = manifest.releases[0].targets.remove("linux_x86_64").unwrap();
+        release.targets.insert("linux_x86_64".to_owned(), linux);
+        manifest.releases.push(release);
+        assert_eq!(versions_for(&manifest, "linux_x86_64"), ["0.3.1"]);
+        assert_eq!(versions_for(&manifest, "windows_x86_64"), ["0.3.0"]);
+    }
+}
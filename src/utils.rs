@@ -1,19 +1,21 @@
 use std::{
     fs::{self, File},
-    io::Write,
+    io::{BufReader, Read, Write},
     path::Path,
+    process,
 };
 
-use log::debug;
-use minreq;
+use log::{debug, error};
 use pbr::{ProgressBar, Units};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
 
 pub fn download_to(url: &str, save_path: &str) {
     let save_path = Path::new(save_path);
 
     let resp = minreq::get(url)
         .send_lazy()
-        .expect(&format!("请求 {} 失败", url));
+        .unwrap_or_else(|_| panic!("请求 {} 失败", url));
     if resp.status_code != 200 {
         panic!("请求 {} 失败，状态码 {}", url, resp.status_code);
     }
@@ -43,7 +45,102 @@ pub fn download_to(url: &str, save_path: &str) {
     debug!("下载 {} 保存到 {}", url, save_path.display());
 
     let mut f = File::create(&save_path).unwrap();
-    f.write(buf.as_slice()).unwrap();
+    f.write_all(buf.as_slice()).unwrap();
+}
+
+//边下载边写入文件，返回文件的 sha256
+pub fn download_file<P: AsRef<Path>>(url: &str, save_path: P) -> String {
+    let save_path = save_path.as_ref();
+
+    let mut resp = minreq::get(url)
+        .send_lazy()
+        .unwrap_or_else(|_| panic!("请求 {} 失败", url));
+    if resp.status_code != 200 {
+        panic!("请求 {} 失败，状态码 {}", url, resp.status_code);
+    }
+    let data_length: u64 = resp
+        .headers
+        .get("content-length")
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(0);
+    let mut bar = ProgressBar::new(data_length);
+    bar.set_units(Units::Bytes);
+    bar.set_max_refresh_rate(Some(std::time::Duration::from_millis(100)));
+
+    let mut f = File::create(save_path).unwrap();
+    let mut hasher = Sha256::new();
+    let mut buf = [0; 64 * 1024];
+    loop {
+        let n = resp
+            .read(&mut buf)
+            .unwrap_or_else(|e| panic!("下载 {} 失败，{}", url, e));
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        f.write_all(&buf[..n]).unwrap();
+        bar.add(n as u64);
+    }
+    bar.finish_println("");
+
+    debug!("下载 {} 保存到 {}", url, save_path.display());
+
+    hasher
+        .finalize()
+        .iter()
+        .map(|x| format!("{:02x}", x))
+        .collect()
+}
+
+pub fn get_json<T: for<'de> Deserialize<'de>>(url: &str) -> Result<T, String> {
+    debug!("获取 {}", url);
+    let resp = minreq::get(url)
+        .with_header("User-Agent", "mirua")
+        .send()
+        .map_err(|e| format!("请求 {} 失败，{}", url, e))?;
+    if resp.status_code != 200 {
+        return Err(format!("请求 {} 失败，状态码 {}", url, resp.status_code));
+    }
+    resp.json::<T>()
+        .map_err(|e| format!("解析 {} 失败，{}", url, e))
+}
+
+//校验文件的格式为 "<sha256>  <文件名>"
+pub fn get_checksum(url: &str) -> Result<String, String> {
+    debug!("获取 {}", url);
+    let resp = minreq::get(url)
+        .with_header("User-Agent", "mirua")
+        .send()
+        .map_err(|e| format!("请求 {} 失败，{}", url, e))?;
+    if resp.status_code != 200 {
+        return Err(format!("请求 {} 失败，状态码 {}", url, resp.status_code));
+    }
+    resp.as_str()
+        .ok()
+        .and_then(|x| x.split_whitespace().next())
+        .filter(|x| x.len() == 64 && x.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|x| x.to_owned())
+        .ok_or_else(|| format!("{} 中没有 sha256", url))
+}
+
+//按列对齐输出表格，中文按两格宽度算
+pub fn print_table<const N: usize>(header: [&str; N], rows: &[[&str; N]]) {
+    let width = |s: &str| -> usize { s.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum() };
+    let mut widths = header.map(width);
+    for row in rows.iter() {
+        for (w, cell) in widths.iter_mut().zip(row.iter()) {
+            *w = (*w).max(width(cell));
+        }
+    }
+
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, w)| format!("{}{}", cell, " ".repeat(w - width(cell))))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
 }
 
 pub fn ensure_dir<P: AsRef<Path>>(dir_path: P) {
@@ -55,3 +152,64 @@ pub fn ensure_dir<P: AsRef<Path>>(dir_path: P) {
         panic!("当前目录下存在非目录 {:?}", dir_path);
     }
 }
+
+//用户输入有误等不需要调用栈的错误，输出后直接退出
+pub fn fail(message: String) -> ! {
+    error!("{}", message);
+    process::exit(1);
+}
+
+//unix 上为 tar.gz，windows 上为 zip，压缩包损坏时返回错误，已经解压的文件留给调用者清理
+#[cfg(unix)]
+pub fn extract(archive_path: &Path, extract_path: &Path) -> Result<(), String> {
+    use flate2::read::GzDecoder;
+    use tar::Archive;
+
+    let archive = File::open(archive_path)
+        .map_err(|e| format!("打开 {} 失败，{}", archive_path.display(), e))?;
+    let mut archive = Archive::new(GzDecoder::new(BufReader::new(archive)));
+    //unpack 会拒绝包含 .. 或绝对路径的条目
+    archive
+        .unpack(extract_path)
+        .map_err(|e| format!("解压 {} 失败，{}", archive_path.display(), e))
+}
+
+#[cfg(windows)]
+pub fn extract(archive_path: &Path, extract_path: &Path) -> Result<(), String> {
+    use std::io;
+    use zip::ZipArchive;
+
+    let error = |e: &dyn std::fmt::Display| format!("解压 {} 失败，{}", archive_path.display(), e);
+    let archive = File::open(archive_path)
+        .map_err(|e| format!("打开 {} 失败，{}", archive_path.display(), e))?;
+    let mut archive = ZipArchive::new(BufReader::new(archive)).map_err(|e| error(&e))?;
+    let mut bar = ProgressBar::new(archive.len() as u64);
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(|e| error(&e))?;
+        //enclosed_name 会拒绝跳出解压目录的路径
+        let outpath = match file.enclosed_name() {
+            Some(x) => extract_path.join(x),
+            None => {
+                return Err(format!(
+                    "{} 中存在非法路径 {}",
+                    archive_path.display(),
+                    file.name()
+                ))
+            }
+        };
+
+        if file.is_dir() {
+            fs::create_dir_all(&outpath).map_err(|e| error(&e))?;
+        } else {
+            if let Some(p) = outpath.parent() {
+                fs::create_dir_all(p).map_err(|e| error(&e))?;
+            }
+            let mut outfile = File::create(outpath).map_err(|e| error(&e))?;
+            io::copy(&mut file, &mut outfile).map_err(|e| error(&e))?;
+        }
+        bar.inc();
+    }
+
+    bar.finish_println("");
+    Ok(())
+}